@@ -0,0 +1,669 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+mod assembler_tests;
+
+use crate::instruction::Instruction;
+use crate::rom::ROM_START;
+
+// Assembles source in the style of Octo (https://github.com/JohnEarnest/Octo)
+// into a ROM image that loads at 0x200. Supported:
+//
+//   : name                  a label
+//   :alias name v3          another name for a register
+//   :const name 12          a named number
+//   :macro name a b { ... } a macro; `name x y` expands the body with a and b
+//                           replaced by x and y
+//   12 0xFF 0b1010 -1       data bytes
+//   "..####.."              a sprite row, 8 or 16 pixels of . and # (or 0
+//                           and 1), giving one or two bytes
+//   # ...                   a comment to the end of the line
+//
+// and Octo's statements: clear, return (or ;), exit, hires, lores,
+// scroll-down N, scroll-up N, scroll-left, scroll-right, plane N, audio,
+// jump L, jump0 L, a bare label name to call it, sprite vx vy N, bcd vx,
+// save vx, load vx, save vx - vy, load vx - vy, saveflags vx, loadflags vx,
+// vx := vy|N|random N|delay|key, vx += -= =- |= &= ^= >>= <<=, i := L,
+// i := long L, i := hex vx, i := bighex vx, i += vx, delay := vx,
+// buzzer := vx, pitch := vx, `if COND then STATEMENT`,
+// `if COND begin ... else ... end` and `loop ... while COND ... again`.
+// Conditions are vx == N|vy, vx != N|vy, vx key and vx -key.
+//
+// If a label `main` exists the ROM starts with `jump main`, as Octo does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+pub struct Assembly {
+    pub rom: Vec<u8>,
+    // Every label with its address.
+    pub symbols: BTreeMap<String, u16>
+}
+
+impl Assembly {
+    // One `ADDR NAME` line per label, in address order.
+    pub fn symbol_map(&self) -> String {
+        let mut symbols: Vec<(&u16, &String)> = self.symbols.iter().map(|(name, address)| (address, name)).collect();
+        symbols.sort();
+        symbols.iter().map(|(address, name)| format!("{:04X} {}\n", address, name)).collect()
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    let tokens = tokenize(source)?;
+    let mut assembler = Assembler {
+        tokens: tokens.into(),
+        rom: Vec::new(),
+        labels: BTreeMap::new(),
+        aliases: HashMap::new(),
+        constants: HashMap::new(),
+        macros: HashMap::new(),
+        fixups: Vec::new(),
+        blocks: Vec::new(),
+        expansions: 0,
+        line: 1
+    };
+    if source_defines_main(&assembler.tokens) {
+        assembler.fixups.push(Fixup { offset: 0, label: String::from("main"), long: false, line: 1 });
+        assembler.emit(Instruction::Jump { nnn: 0 });
+    }
+    while let Some(token) = assembler.next() {
+        assembler.statement(token)?;
+    }
+    assembler.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    line: usize
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, AsmError> {
+    let mut tokens = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut rest = text;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with('#') {
+                break;
+            }
+            let end = if let Some(row) = rest.strip_prefix('"') {
+                match row.find('"') {
+                    Some(close) => close + 2,
+                    None => return Err(AsmError { line, message: String::from("unterminated sprite row") })
+                }
+            } else {
+                rest.find(char::is_whitespace).unwrap_or(rest.len())
+            };
+            tokens.push(Token { text: rest[..end].to_string(), line });
+            rest = &rest[end..];
+        }
+    }
+    Ok(tokens)
+}
+
+fn source_defines_main(tokens: &VecDeque<Token>) -> bool {
+    tokens.iter().zip(tokens.iter().skip(1)).any(|(colon, name)| colon.text == ":" && name.text == "main")
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>
+}
+
+// A 12-bit (or with `long`, 16-bit) address field to fill in once every
+// label is known.
+struct Fixup {
+    offset: usize,
+    label: String,
+    long: bool,
+    line: usize
+}
+
+enum Block {
+    // The jump at `offset` is taken when the condition is false.
+    If { offset: usize, line: usize },
+    Else { offset: usize, line: usize },
+    // `breaks` are the jumps out of the loop that `while` added.
+    Loop { start: u16, breaks: Vec<usize>, line: usize }
+}
+
+// Skip instructions for a condition: the first skips when it holds, the
+// second when it does not.
+type Condition = (Instruction, Instruction);
+
+const KEYWORDS: [&str; 37] = [
+    "clear", "return", "exit", "hires", "lores", "scroll-down", "scroll-up", "scroll-left", "scroll-right",
+    "plane", "audio", "jump", "jump0", "sprite", "bcd", "save", "load", "saveflags", "loadflags", "i", "I",
+    "delay", "buzzer", "pitch", "key", "random", "long", "hex", "bighex", "if", "then", "begin", "else",
+    "end", "loop", "while", "again"
+];
+
+// Macros that expand to themselves would otherwise never finish.
+const MAX_EXPANSIONS: usize = 10_000;
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    rom: Vec<u8>,
+    labels: BTreeMap<String, u16>,
+    aliases: HashMap<String, u8>,
+    constants: HashMap<String, i32>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    expansions: usize,
+    // The line of the token read last, for error messages.
+    line: usize
+}
+
+impl Assembler {
+    fn error<T>(&self, message: String) -> Result<T, AsmError> {
+        Err(AsmError { line: self.line, message })
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.pop_front()?;
+        self.line = token.line;
+        Some(token.text)
+    }
+
+    fn expect_token(&mut self, what: &str) -> Result<String, AsmError> {
+        match self.next() {
+            Some(token) => Ok(token),
+            None => self.error(format!("expected {} at the end of the source", what))
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AsmError> {
+        let token = self.expect_token(&format!("'{}'", expected))?;
+        if token != expected {
+            return self.error(format!("expected '{}', got '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.tokens.front().is_some_and(|token| token.text == text)
+    }
+
+    fn address(&self) -> u16 {
+        (ROM_START + self.rom.len()) as u16
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.rom.extend_from_slice(&instruction.encode().to_be_bytes());
+    }
+
+    fn statement(&mut self, token: String) -> Result<(), AsmError> {
+        if let Some(expansion) = self.macros.get(&token) {
+            let mut args = HashMap::new();
+            let params = expansion.params.clone();
+            for param in params {
+                let arg = self.expect_token(&format!("an argument for {}", token))?;
+                args.insert(param, arg);
+            }
+            self.expansions += 1;
+            if self.expansions > MAX_EXPANSIONS {
+                return self.error(format!("macro {} keeps expanding", token));
+            }
+            let line = self.line;
+            let body = &self.macros[&token].body;
+            for body_token in body.iter().rev() {
+                let text = args.get(&body_token.text).cloned().unwrap_or_else(|| body_token.text.clone());
+                self.tokens.push_front(Token { text, line });
+            }
+            return Ok(());
+        }
+
+        match token.as_str() {
+            ":" => {
+                let name = self.expect_token("a label name")?;
+                self.check_name(&name)?;
+                let address = self.address();
+                if self.labels.insert(name.clone(), address).is_some() {
+                    return self.error(format!("label {} is defined twice", name));
+                }
+            },
+            ":alias" => {
+                let name = self.expect_token("an alias name")?;
+                self.check_name(&name)?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+            },
+            ":const" => {
+                let name = self.expect_token("a constant name")?;
+                self.check_name(&name)?;
+                let value = self.number()?;
+                self.constants.insert(name, value);
+            },
+            ":macro" => self.define_macro()?,
+            "clear" => self.emit(Instruction::Clear),
+            "return" | ";" => self.emit(Instruction::Return),
+            "exit" => self.emit(Instruction::Exit),
+            "hires" => self.emit(Instruction::HighRes),
+            "lores" => self.emit(Instruction::LowRes),
+            "scroll-left" => self.emit(Instruction::ScrollLeft),
+            "scroll-right" => self.emit(Instruction::ScrollRight),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollDown { n });
+            },
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollUp { n });
+            },
+            "plane" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::SelectPlanes { n });
+            },
+            "audio" => self.emit(Instruction::LoadAudio),
+            "jump" => self.address_operand(|nnn| Instruction::Jump { nnn })?,
+            "jump0" => self.address_operand(|nnn| Instruction::JumpOffset { nnn })?,
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(Instruction::Draw { x, y, n });
+            },
+            "bcd" => self.register_operand(|x| Instruction::StoreBcd { x })?,
+            "saveflags" => self.register_operand(|x| Instruction::StoreFlags { x })?,
+            "loadflags" => self.register_operand(|x| Instruction::LoadFlags { x })?,
+            "save" | "load" => {
+                let x = self.register()?;
+                let instruction = if self.peek_is("-") {
+                    self.next();
+                    let y = self.register()?;
+                    if token == "save" { Instruction::StoreRange { x, y } } else { Instruction::LoadRange { x, y } }
+                } else if token == "save" {
+                    Instruction::StoreRegs { x }
+                } else {
+                    Instruction::LoadRegs { x }
+                };
+                self.emit(instruction);
+            },
+            "i" | "I" => self.assign_i()?,
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(match token.as_str() {
+                    "delay" => Instruction::SetDelay { x },
+                    "buzzer" => Instruction::SetSound { x },
+                    _ => Instruction::SetPitch { x }
+                });
+            },
+            "if" => self.conditional()?,
+            "else" => {
+                let line = self.line;
+                match self.blocks.pop() {
+                    Some(Block::If { offset, .. }) => {
+                        let jump = self.rom.len();
+                        self.emit(Instruction::Jump { nnn: 0 });
+                        self.patch(offset, self.address())?;
+                        self.blocks.push(Block::Else { offset: jump, line });
+                    },
+                    _ => return self.error(String::from("else without if ... begin"))
+                }
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { offset, .. }) | Some(Block::Else { offset, .. }) => self.patch(offset, self.address())?,
+                _ => return self.error(String::from("end without if ... begin"))
+            },
+            "loop" => {
+                let start = self.address();
+                let line = self.line;
+                self.blocks.push(Block::Loop { start, breaks: Vec::new(), line });
+            },
+            "while" => {
+                let (skip_if_true, _) = self.condition()?;
+                self.emit(skip_if_true);
+                let offset = self.rom.len();
+                self.emit(Instruction::Jump { nnn: 0 });
+                match self.blocks.iter_mut().rev().find(|block| matches!(block, Block::Loop { .. })) {
+                    Some(Block::Loop { breaks, .. }) => breaks.push(offset),
+                    _ => return self.error(String::from("while outside of a loop"))
+                }
+            },
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, breaks, .. }) => {
+                    let nnn = self.block_target(start)?;
+                    self.emit(Instruction::Jump { nnn });
+                    for offset in breaks {
+                        self.patch(offset, self.address())?;
+                    }
+                },
+                _ => return self.error(String::from("again without loop"))
+            },
+            _ if token.starts_with('"') => self.sprite_row(&token)?,
+            _ if self.register_name(&token).is_some() => {
+                let x = self.register_name(&token).unwrap();
+                self.assign_register(x)?;
+            },
+            _ if token.starts_with(|c: char| c.is_ascii_digit() || c == '-') || self.constants.contains_key(&token) => {
+                let value = self.value(&token)?;
+                let byte = self.byte(value)?;
+                self.rom.push(byte);
+            },
+            _ if token.starts_with(':') => return self.error(format!("unknown directive {}", token)),
+            _ => {
+                // Anything else names a subroutine to call.
+                self.check_name(&token)?;
+                self.fixup(&token, false);
+                self.emit(Instruction::Call { nnn: 0 });
+            }
+        }
+        Ok(())
+    }
+
+    fn define_macro(&mut self) -> Result<(), AsmError> {
+        let name = self.expect_token("a macro name")?;
+        self.check_name(&name)?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.expect_token("'{'")?;
+            if token == "{" {
+                break;
+            }
+            params.push(token);
+        }
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = match self.tokens.pop_front() {
+                Some(token) => token,
+                None => return self.error(format!("macro {} has no closing '}}'", name))
+            };
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 { break }
+                },
+                _ => {}
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { params, body });
+        Ok(())
+    }
+
+    fn assign_i(&mut self) -> Result<(), AsmError> {
+        let op = self.expect_token("':=' or '+='")?;
+        match op.as_str() {
+            ":=" => {
+                if self.peek_is("long") {
+                    self.next();
+                    let target = self.expect_token("an address")?;
+                    let address = self.target(&target, true)?;
+                    self.emit(Instruction::LoadILong);
+                    self.rom.extend_from_slice(&address.to_be_bytes());
+                } else if self.peek_is("hex") || self.peek_is("bighex") {
+                    let big = self.next().unwrap() == "bighex";
+                    let x = self.register()?;
+                    self.emit(if big { Instruction::LoadBigFont { x } } else { Instruction::LoadFont { x } });
+                } else {
+                    self.address_operand(|nnn| Instruction::LoadI { nnn })?;
+                }
+            },
+            "+=" => self.register_operand(|x| Instruction::AddI { x })?,
+            other => return self.error(format!("expected ':=' or '+=' after i, got '{}'", other))
+        }
+        Ok(())
+    }
+
+    fn assign_register(&mut self, x: u8) -> Result<(), AsmError> {
+        let op = self.expect_token("an operator")?;
+        let operand = self.expect_token("an operand")?;
+        let y = self.register_name(&operand);
+        let instruction = match (op.as_str(), y) {
+            (":=", Some(y)) => Instruction::LoadReg { x, y },
+            (":=", None) => match operand.as_str() {
+                "random" => {
+                    let token = self.expect_token("a mask")?;
+                    let nn = self.value(&token).and_then(|value| self.byte(value))?;
+                    Instruction::Random { x, nn }
+                },
+                "delay" => Instruction::LoadDelay { x },
+                "key" => Instruction::WaitKey { x },
+                _ => Instruction::LoadImm { x, nn: self.value(&operand).and_then(|value| self.byte(value))? }
+            },
+            ("+=", Some(y)) => Instruction::AddReg { x, y },
+            ("+=", None) => Instruction::AddImm { x, nn: self.value(&operand).and_then(|value| self.byte(value))? },
+            ("-=", Some(y)) => Instruction::SubReg { x, y },
+            ("-=", None) => Instruction::AddImm { x, nn: self.value(&operand).and_then(|value| self.byte(-value))? },
+            ("=-", Some(y)) => Instruction::SubNeg { x, y },
+            ("|=", Some(y)) => Instruction::Or { x, y },
+            ("&=", Some(y)) => Instruction::And { x, y },
+            ("^=", Some(y)) => Instruction::Xor { x, y },
+            (">>=", Some(y)) => Instruction::ShiftRight { x, y },
+            ("<<=", Some(y)) => Instruction::ShiftLeft { x, y },
+            ("=-", None) | ("|=", None) | ("&=", None) | ("^=", None) | (">>=", None) | ("<<=", None) => {
+                return self.error(format!("{} needs a register, got '{}'", op, operand));
+            },
+            _ => return self.error(format!("unknown operator '{}'", op))
+        };
+        self.emit(instruction);
+        Ok(())
+    }
+
+    fn conditional(&mut self) -> Result<(), AsmError> {
+        let (skip_if_true, skip_if_false) = self.condition()?;
+        let line = self.line;
+        match self.expect_token("then or begin")?.as_str() {
+            "then" => self.emit(skip_if_false),
+            "begin" => {
+                self.emit(skip_if_true);
+                let offset = self.rom.len();
+                self.emit(Instruction::Jump { nnn: 0 });
+                self.blocks.push(Block::If { offset, line });
+            },
+            other => return self.error(format!("expected then or begin, got '{}'", other))
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition, AsmError> {
+        let x = self.register()?;
+        let op = self.expect_token("a comparison")?;
+        match op.as_str() {
+            "key" => return Ok((Instruction::SkipKey { x }, Instruction::SkipNotKey { x })),
+            "-key" => return Ok((Instruction::SkipNotKey { x }, Instruction::SkipKey { x })),
+            "==" | "!=" => {},
+            "<" | ">" | "<=" | ">=" => return self.error(format!("'{}' is not supported, only ==, !=, key and -key", op)),
+            other => return self.error(format!("expected a comparison, got '{}'", other))
+        }
+        let operand = self.expect_token("an operand")?;
+        let (equal, unequal) = match self.register_name(&operand) {
+            Some(y) => (Instruction::SkipEqReg { x, y }, Instruction::SkipNeReg { x, y }),
+            None => {
+                let nn = self.value(&operand).and_then(|value| self.byte(value))?;
+                (Instruction::SkipEqImm { x, nn }, Instruction::SkipNeImm { x, nn })
+            }
+        };
+        Ok(if op == "==" { (equal, unequal) } else { (unequal, equal) })
+    }
+
+    fn sprite_row(&mut self, token: &str) -> Result<(), AsmError> {
+        let pixels = &token[1..token.len() - 1];
+        if pixels.len() != 8 && pixels.len() != 16 {
+            return self.error(format!("a sprite row is 8 or 16 pixels, got {}", pixels.len()));
+        }
+        let mut bits: u16 = 0;
+        for c in pixels.chars() {
+            bits = (bits << 1) | match c {
+                '#' | '1' => 1,
+                '.' | '0' => 0,
+                other => return self.error(format!("unexpected '{}' in a sprite row, use . and #", other))
+            };
+        }
+        if pixels.len() == 16 {
+            self.rom.push((bits >> 8) as u8);
+        }
+        self.rom.push(bits as u8);
+        Ok(())
+    }
+
+    fn register_operand<F: Fn(u8) -> Instruction>(&mut self, make: F) -> Result<(), AsmError> {
+        let x = self.register()?;
+        self.emit(make(x));
+        Ok(())
+    }
+
+    fn address_operand<F: Fn(u16) -> Instruction>(&mut self, make: F) -> Result<(), AsmError> {
+        let target = self.expect_token("an address")?;
+        let nnn = self.target(&target, false)?;
+        self.emit(make(nnn));
+        Ok(())
+    }
+
+    // A number, constant or label. Labels are filled in by finish, so they
+    // give 0 for now.
+    fn target(&mut self, token: &str, long: bool) -> Result<u16, AsmError> {
+        let max = if long { 0xFFFF } else { 0xFFF };
+        if token.starts_with(|c: char| c.is_ascii_digit()) || self.constants.contains_key(token) {
+            let value = self.value(token)?;
+            if value < 0 || value > max {
+                return self.error(format!("address {} is out of range", token));
+            }
+            return Ok(value as u16);
+        }
+        self.check_name(token)?;
+        self.fixup(token, long);
+        Ok(0)
+    }
+
+    fn fixup(&mut self, label: &str, long: bool) {
+        // A long address is the word after F000.
+        let offset = if long { self.rom.len() + 2 } else { self.rom.len() };
+        self.fixups.push(Fixup { offset, label: label.to_string(), long, line: self.line });
+    }
+
+    // Points the address field of the instruction at `offset` at `address`.
+    fn patch(&mut self, offset: usize, address: u16) -> Result<(), AsmError> {
+        let address = self.block_target(address)?;
+        self.rom[offset] = (self.rom[offset] & 0xF0) | (address >> 8) as u8;
+        self.rom[offset + 1] = address as u8;
+        Ok(())
+    }
+
+    // Blocks are built from jumps and skips, which only reach the first 4 KiB.
+    fn block_target(&self, address: u16) -> Result<u16, AsmError> {
+        if address > 0xFFF {
+            return self.error(format!("block jumps to {:#X}, past the 0xFFF a jump can reach", address));
+        }
+        Ok(address)
+    }
+
+    fn register(&mut self) -> Result<u8, AsmError> {
+        let token = self.expect_token("a register")?;
+        match self.register_name(&token) {
+            Some(x) => Ok(x),
+            None => self.error(format!("expected a register, got '{}'", token))
+        }
+    }
+
+    fn register_name(&self, token: &str) -> Option<u8> {
+        if let Some(&x) = self.aliases.get(token) {
+            return Some(x);
+        }
+        let lower = token.to_lowercase();
+        if lower.len() == 2 && lower.starts_with('v') {
+            return u8::from_str_radix(&lower[1..], 16).ok();
+        }
+        None
+    }
+
+    fn number(&mut self) -> Result<i32, AsmError> {
+        let token = self.expect_token("a number")?;
+        self.value(&token)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AsmError> {
+        let value = self.number()?;
+        if !(0..=15).contains(&value) {
+            return self.error(format!("expected 0 to 15, got {}", value));
+        }
+        Ok(value as u8)
+    }
+
+    fn value(&self, token: &str) -> Result<i32, AsmError> {
+        if let Some(&value) = self.constants.get(token) {
+            return Ok(value);
+        }
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token)
+        };
+        let parsed = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            i32::from_str_radix(hex, 16)
+        } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+            i32::from_str_radix(binary, 2)
+        } else {
+            digits.parse::<i32>()
+        };
+        match parsed {
+            Ok(value) if negative => Ok(-value),
+            Ok(value) => Ok(value),
+            Err(_) => self.error(format!("expected a number, got '{}'", token))
+        }
+    }
+
+    // Bytes may be written signed or unsigned.
+    fn byte(&self, value: i32) -> Result<u8, AsmError> {
+        if !(-128..=255).contains(&value) {
+            return self.error(format!("{} does not fit in a byte", value));
+        }
+        Ok(value as u8)
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), AsmError> {
+        let reserved = self.register_name(name).is_some()
+            || !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || KEYWORDS.contains(&name);
+        if reserved {
+            return self.error(format!("'{}' cannot be used as a name", name));
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Assembly, AsmError> {
+        match self.blocks.last() {
+            Some(Block::If { line, .. }) | Some(Block::Else { line, .. }) => {
+                return Err(AsmError { line: *line, message: String::from("if ... begin is missing its end") });
+            },
+            Some(Block::Loop { line, .. }) => {
+                return Err(AsmError { line: *line, message: String::from("loop is missing its again") });
+            },
+            None => {}
+        }
+        for fixup in std::mem::take(&mut self.fixups) {
+            let address = match self.labels.get(&fixup.label) {
+                Some(&address) => address,
+                None => return Err(AsmError { line: fixup.line, message: format!("undefined label {}", fixup.label) })
+            };
+            if fixup.long {
+                self.rom[fixup.offset..fixup.offset + 2].copy_from_slice(&address.to_be_bytes());
+            } else if address > 0xFFF {
+                return Err(AsmError { line: fixup.line, message: format!("label {} at {:#X} needs i := long", fixup.label, address) });
+            } else {
+                self.patch(fixup.offset, address)?;
+            }
+        }
+        let max = 0x10000 - ROM_START;
+        if self.rom.len() > max {
+            return Err(AsmError { line: self.line, message: format!("program is {} bytes, but at most {} fit in memory", self.rom.len(), max) });
+        }
+        Ok(Assembly { rom: self.rom, symbols: self.labels })
+    }
+}
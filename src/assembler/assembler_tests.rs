@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod assembler_tests {
+    use std::fs;
+    use crate::assembler::*;
+
+    #[test]
+    fn test_fixture_source() {
+        let source = "
+            v6 := key
+            : spin
+            jump spin   # the fixture repeats the jump
+            jump spin
+        ";
+        let assembly = assemble(source).unwrap();
+        assert_eq!(fs::read("E06KeypadLoop.ch8").unwrap(), assembly.rom);
+    }
+
+    #[test]
+    fn test_statements_and_data() {
+        let source = r#"
+            :alias x v1
+            :const SPEED 3
+            : main
+                i := ball
+                x := 0
+                loop
+                    x += SPEED
+                    if x == 60 then x := 0
+                    sprite x x 2
+                    while vf != 1
+                again
+                exit
+            : ball
+                "..####.."
+                0b00111100 -1
+        "#;
+        let assembly = assemble(source).unwrap();
+        assert_eq!(vec![
+            0x12, 0x02, // jump main
+            0xA2, 0x16, // i := ball
+            0x61, 0x00, // x := 0
+            0x71, 0x03, // x += SPEED
+            0x41, 0x3C, // if x == 60 then
+            0x61, 0x00, //   x := 0
+            0xD1, 0x12, // sprite x x 2
+            0x4F, 0x01, // while vf != 1
+            0x12, 0x14, //   jump out of the loop
+            0x12, 0x06, // again
+            0x00, 0xFD, // exit
+            0x3C, 0x3C, 0xFF, // ball
+        ], assembly.rom);
+        assert_eq!("0202 main\n0216 ball\n", assembly.symbol_map());
+    }
+
+    #[test]
+    fn test_blocks_and_macros() {
+        let source = "
+            :macro bump reg amount { reg += amount }
+            : main
+            if v0 key begin
+                bump v2 1
+            else
+                bump v2 -1
+                v3 := random 0x0F
+            end
+            i := long data
+            ;
+            : data 7
+        ";
+        let assembly = assemble(source).unwrap();
+        assert_eq!(vec![
+            0x12, 0x02,
+            0xE0, 0x9E, 0x12, 0x0A, // if v0 key begin
+            0x72, 0x01, 0x12, 0x0E, // bump v2 1, else
+            0x72, 0xFF, 0xC3, 0x0F, // bump v2 -1, v3 := random
+            0xF0, 0x00, 0x02, 0x14, // i := long data
+            0x00, 0xEE,
+            0x07
+        ], assembly.rom);
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let error = |source: &str| assemble(source).err().unwrap().to_string();
+        assert_eq!("line 3: undefined label missing", error("v0 := 1\n\njump missing"));
+        assert_eq!("line 2: expected a register, got 'vg'", error("clear\nsprite vg v1 5"));
+        assert_eq!("line 1: 300 does not fit in a byte", error("v0 := 300"));
+        assert_eq!("line 2: if ... begin is missing its end", error("\nif v0 == 1 begin\nclear"));
+        assert_eq!("line 2: label a is defined twice", error(": a\n: a"));
+        assert_eq!("line 1: 'sprite' cannot be used as a name", error(": sprite"));
+
+        // Structured blocks jump, so they have to end below 0x1000.
+        let padding = "0 ".repeat(0xE00 - 4);
+        assert_eq!("line 3: block jumps to 0x1000, past the 0xFFF a jump can reach",
+            error(&format!("if v0 == 1 begin\n{}\nend", padding)));
+        assert_eq!("line 3: block jumps to 0x1000, past the 0xFFF a jump can reach",
+            error(&format!("{}\nloop\nagain", "0 ".repeat(0xE00))));
+        assert!(assemble(&format!("if v0 == 1 begin\n{}\nend", "0 ".repeat(0xE00 - 5))).is_ok());
+    }
+}
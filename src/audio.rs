@@ -0,0 +1,271 @@
+use std::f32::consts::PI;
+use std::io::{self, Seek, SeekFrom, Write};
+mod audio_tests;
+
+// The beep played while the sound timer runs, and where it goes. Generating
+// the samples is kept apart from any audio device so it can be tested, and so
+// frontends other than SDL can reuse it.
+
+// What the machine played during one frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sound {
+    // Whether the sound timer was running.
+    pub sounding: bool,
+    // The XO-CHIP sample loop, once a program has loaded one; until then the
+    // sink plays its own tone.
+    pub pattern: Option<Pattern>
+}
+
+impl Sound {
+    pub fn buzzer(sounding: bool) -> Sound {
+        Sound { sounding, pattern: None }
+    }
+}
+
+// 128 1-bit samples, most significant bit of the first byte first, played
+// in a loop at `rate` bits per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pattern {
+    pub bits: [u8; 16],
+    pub rate: f64
+}
+
+impl Pattern {
+    fn bit(&self, index: usize) -> bool {
+        self.bits[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+}
+
+// Where the machine sends its sound, once installed with
+// Chip8::set_audio_sink. It is told about every emulated frame, 1/60th of a
+// second, when the timers tick.
+pub trait AudioSink {
+    // What sounded during the frame that just ended.
+    fn frame(&mut self, sound: Sound);
+
+    // Writes out anything held back. Called once, when emulation ends.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Drops the sound.
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn frame(&mut self, _sound: Sound) {}
+}
+
+// Sends the sound to several sinks, such as a speaker and a recording.
+impl AudioSink for Vec<Box<dyn AudioSink>> {
+    fn frame(&mut self, sound: Sound) {
+        for sink in self.iter_mut() {
+            sink.frame(sound);
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for sink in self.iter_mut() {
+            let finished = sink.finish();
+            if result.is_ok() {
+                result = finished;
+            }
+        }
+        result
+    }
+}
+
+// Records the beep as a 16-bit mono WAV file. The sizes in the header are
+// only known at the end, so finish goes back and fills them in. Write errors
+// stop the recording; finish reports them.
+pub struct WavSink<W: Write + Seek> {
+    out: W,
+    tone: Tone,
+    frames: u64,
+    data_len: u32,
+    error: Option<io::Error>
+}
+
+const WAV_HEADER_LEN: u32 = 44;
+
+impl<W: Write + Seek> WavSink<W> {
+    pub fn new(mut out: W, settings: ToneSettings, sample_rate: u32) -> io::Result<WavSink<W>> {
+        out.write_all(&wav_header(sample_rate, 0))?;
+        Ok(WavSink { out, tone: Tone::new(settings, sample_rate), frames: 0, data_len: 0, error: None })
+    }
+
+    // The samples making up `frame`, spread so that 60 frames are exactly one
+    // second.
+    fn samples_in_frame(&self, frame: u64) -> usize {
+        let rate = self.tone.get_sample_rate() as u64;
+        (rate * (frame % 60 + 1) / 60 - rate * (frame % 60) / 60) as usize
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavSink<W> {
+    fn frame(&mut self, sound: Sound) {
+        let mut samples = vec![0; self.samples_in_frame(self.frames)];
+        self.frames += 1;
+        self.tone.set_sound(sound);
+        self.tone.fill(&mut samples);
+        if self.error.is_some() {
+            return;
+        }
+        let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        match self.out.write_all(&bytes) {
+            Ok(()) => self.data_len = self.data_len.saturating_add(bytes.len() as u32),
+            Err(e) => self.error = Some(e)
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.seek(SeekFrom::Start(0))?;
+        self.out.write_all(&wav_header(self.tone.get_sample_rate(), self.data_len))?;
+        self.out.seek(SeekFrom::Start((WAV_HEADER_LEN + self.data_len) as u64))?;
+        self.out.flush()
+    }
+}
+
+fn wav_header(sample_rate: u32, data_len: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(WAV_HEADER_LEN as usize);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(WAV_HEADER_LEN - 8 + data_len).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // mono
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // bytes per second
+    header.extend_from_slice(&2u16.to_le_bytes()); // bytes per sample
+    header.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sine,
+    Triangle
+}
+
+impl Waveform {
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name.to_lowercase().as_str() {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None
+        }
+    }
+
+    // The wave at `phase`, a fraction of one period, from -1 to 1.
+    fn at(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneSettings {
+    pub frequency: f32,
+    pub waveform: Waveform,
+    // From 0 (silent) to 1 (full scale).
+    pub volume: f32
+}
+
+impl Default for ToneSettings {
+    fn default() -> ToneSettings {
+        ToneSettings { frequency: 440.0, waveform: Waveform::default(), volume: 0.25 }
+    }
+}
+
+// How long the tone takes to fade in or out. Switching a wave on or off in
+// the middle of a period makes an audible click; a few milliseconds of ramp
+// removes it and is still short enough for one-frame blips.
+const RAMP_SECONDS: f32 = 0.004;
+
+pub struct Tone {
+    settings: ToneSettings,
+    sample_rate: u32,
+    pattern: Option<Pattern>,
+    phase: f32, // A fraction of one period of the wave, or of the whole pattern.
+    gain: f32,
+    playing: bool,
+    muted: bool
+}
+
+impl Tone {
+    pub fn new(settings: ToneSettings, sample_rate: u32) -> Tone {
+        Tone { settings, sample_rate: sample_rate.max(1), pattern: None, phase: 0.0, gain: 0.0, playing: false, muted: false }
+    }
+
+    // Plays the frame's pattern instead of the wave while it has one.
+    pub fn set_sound(&mut self, sound: Sound) {
+        self.playing = sound.sounding;
+        if sound.pattern != self.pattern {
+            self.pattern = sound.pattern;
+            self.phase = 0.0;
+        }
+    }
+
+    pub fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    // Muting fades out like stopping does, but leaves `playing` alone.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // Fills `out` with the next samples, fading towards the current state.
+    pub fn fill(&mut self, out: &mut [i16]) {
+        let target = if self.playing && !self.muted { 1.0 } else { 0.0 };
+        let ramp_step = 1.0 / (RAMP_SECONDS * self.sample_rate as f32).max(1.0);
+        let phase_step = match self.pattern {
+            Some(pattern) => (pattern.rate / 128.0 / self.sample_rate as f64) as f32,
+            None => self.settings.frequency / self.sample_rate as f32
+        };
+        for sample in out.iter_mut() {
+            if self.gain < target {
+                self.gain = (self.gain + ramp_step).min(target);
+            } else if self.gain > target {
+                self.gain = (self.gain - ramp_step).max(target);
+            }
+            if self.gain == 0.0 {
+                // Start every beep at the beginning of a period.
+                self.phase = 0.0;
+                *sample = 0;
+                continue;
+            }
+            let wave = match self.pattern {
+                Some(pattern) => if pattern.bit((self.phase * 128.0) as usize % 128) { 1.0 } else { -1.0 },
+                None => self.settings.waveform.at(self.phase)
+            };
+            let value = wave * self.gain * self.settings.volume.clamp(0.0, 1.0);
+            *sample = (value * i16::MAX as f32) as i16;
+            self.phase = (self.phase + phase_step).fract();
+        }
+    }
+}
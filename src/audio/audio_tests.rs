@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod audio_tests {
+    use std::io::Cursor;
+    use crate::audio::*;
+
+    fn square(frequency: f32) -> Tone {
+        Tone::new(ToneSettings { frequency, waveform: Waveform::Square, volume: 1.0 }, 8000)
+    }
+
+    #[test]
+    fn test_silent_until_played() {
+        let mut tone = square(1000.0);
+        let mut out = [1; 64];
+        tone.fill(&mut out);
+        assert!(out.iter().all(|&sample| sample == 0));
+    }
+
+    /**
+     * At 8000 Hz the 4 ms ramp takes 32 samples, so a 1000 Hz square wave
+     * grows over the first four periods and then swings at full scale.
+    */
+    #[test]
+    fn test_fades_in_and_out() {
+        let mut tone = square(1000.0);
+        tone.set_playing(true);
+        let mut out = [0; 64];
+        tone.fill(&mut out);
+        assert!(out[0] > 0 && out[0] < 2000, "{}", out[0]);
+        assert!(out[0] < out[8] && out[8] < out[16] && out[16] < out[24]);
+        assert_eq!([i16::MAX, i16::MAX, i16::MAX, i16::MAX, -i16::MAX, -i16::MAX, -i16::MAX, -i16::MAX], out[32..40]);
+
+        tone.set_playing(false);
+        tone.fill(&mut out);
+        assert!(out[0].abs() > 30000);
+        assert!(out[4].abs() < out[0].abs());
+        assert!(out[32..].iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn test_mute_and_waveforms() {
+        let mut tone = square(1000.0);
+        tone.set_playing(true);
+        tone.set_muted(true);
+        let mut out = [1; 16];
+        tone.fill(&mut out);
+        assert!(out.iter().all(|&sample| sample == 0));
+        assert!(tone.is_playing());
+
+        let mut triangle = Tone::new(ToneSettings { frequency: 2000.0, waveform: Waveform::Triangle, volume: 0.5 }, 8000);
+        triangle.set_playing(true);
+        let mut out = [0; 48];
+        triangle.fill(&mut out);
+        assert_eq!([-16383, 0, 16383, 0], out[44..48]);
+        assert_eq!(Some(Waveform::Sine), Waveform::from_name("Sine"));
+        assert_eq!(None, Waveform::from_name("noise"));
+    }
+
+    /**
+     * A pattern played at the sample rate gives one bit per sample: bytes of
+     * 0xFF swing high and bytes of 0x00 low, whatever the tone settings.
+    */
+    #[test]
+    fn test_plays_pattern() {
+        let mut tone = square(1000.0);
+        let mut bits = [0; 16];
+        for byte in bits.iter_mut().step_by(2) {
+            *byte = 0xFF;
+        }
+        tone.set_sound(Sound { sounding: true, pattern: Some(Pattern { bits, rate: 8000.0 }) });
+        let mut out = [0; 176];
+        tone.fill(&mut out);
+        assert!(out[32..40].iter().all(|&sample| sample == i16::MAX));
+        assert!(out[40..48].iter().all(|&sample| sample == -i16::MAX));
+        // The loop is 128 bits long, so it starts over at sample 128.
+        assert_eq!(out[32..48], out[160..176]);
+    }
+
+    /**
+     * One silent and one sounding frame at 6000 Hz: 100 samples each, so
+     * 400 bytes of data after the 44 byte header.
+    */
+    #[test]
+    fn test_wav_recording() {
+        let mut wav = WavSink::new(Cursor::new(Vec::new()), ToneSettings::default(), 6000).unwrap();
+        wav.frame(Sound::buzzer(false));
+        wav.frame(Sound::buzzer(true));
+        wav.finish().unwrap();
+        let bytes = wav.out.into_inner();
+        assert_eq!(44 + 400, bytes.len());
+        assert_eq!(b"RIFF", &bytes[0..4]);
+        assert_eq!(436u32.to_le_bytes(), bytes[4..8]);
+        assert_eq!(b"WAVEfmt ", &bytes[8..16]);
+        assert_eq!(6000u32.to_le_bytes(), bytes[24..28]);
+        assert_eq!(400u32.to_le_bytes(), bytes[40..44]);
+        assert!(bytes[44..244].iter().all(|&byte| byte == 0));
+        assert!(bytes[244..].iter().any(|&byte| byte != 0));
+    }
+}
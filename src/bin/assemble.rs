@@ -0,0 +1,66 @@
+extern crate emulator;
+
+use std::path::Path;
+use std::{env, fs, process};
+
+use emulator::assembler;
+
+const USAGE: &str = "\
+Usage: assemble [OPTIONS] <SOURCE>
+
+Assembles Octo-style source into a CHIP-8 ROM.
+
+Options:
+  -o <FILE>          Where to write the ROM (default: SOURCE with .ch8)
+  --symbols <FILE>   Also write every label and its address to FILE
+  -h, --help         Print this help";
+
+fn main() {
+    let mut source_path = None;
+    let mut output = None;
+    let mut symbols = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            "-o" => output = Some(args.next().unwrap_or_else(|| fail("-o needs a value"))),
+            "--symbols" => symbols = Some(args.next().unwrap_or_else(|| fail("--symbols needs a value"))),
+            _ if arg.starts_with('-') => fail(&format!("unknown option '{}'", arg)),
+            _ if source_path.is_some() => fail(&format!("unexpected argument '{}', only one source file can be assembled", arg)),
+            _ => source_path = Some(arg)
+        }
+    }
+    let source_path = source_path.unwrap_or_else(|| fail("no source file given"));
+    let output = output.unwrap_or_else(|| Path::new(&source_path).with_extension("ch8").to_string_lossy().into_owned());
+
+    let source = match fs::read_to_string(&source_path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("assemble: could not read {}: {}", source_path, e);
+            process::exit(1);
+        }
+    };
+    let assembly = match assembler::assemble(&source) {
+        Ok(assembly) => assembly,
+        Err(e) => {
+            eprintln!("assemble: {}: {}", source_path, e);
+            process::exit(1);
+        }
+    };
+    let mut written = fs::write(&output, &assembly.rom).map_err(|e| (output.clone(), e));
+    if let Some(symbols) = symbols {
+        written = written.and_then(|()| fs::write(&symbols, assembly.symbol_map()).map_err(|e| (symbols.clone(), e)));
+    }
+    if let Err((path, e)) = written {
+        eprintln!("assemble: could not write {}: {}", path, e);
+        process::exit(1);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("assemble: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
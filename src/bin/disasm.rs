@@ -0,0 +1,55 @@
+extern crate emulator;
+
+use std::{env, process};
+
+use emulator::disassembler;
+use emulator::platform::Platform;
+use emulator::rom;
+
+const USAGE: &str = "\
+Usage: disasm [OPTIONS] <ROM>
+
+Prints a ROM as CHIP-8 assembly. Code is found by following jumps, calls and
+skips from 0x200; bytes that are never reached are listed as DB data.
+
+Options:
+  --platform <NAME>  Instruction set: chip8 (default), schip or xochip
+  -h, --help         Print this help";
+
+fn main() {
+    let mut platform = Platform::default();
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            "--platform" => {
+                let name = args.next().unwrap_or_default();
+                platform = Platform::from_name(&name).unwrap_or_else(|| {
+                    fail(&format!("unknown platform '{}', expected chip8, schip or xochip", name))
+                });
+            },
+            _ if arg.starts_with('-') => fail(&format!("unknown option '{}'", arg)),
+            _ if path.is_some() => fail(&format!("unexpected argument '{}', only one ROM can be disassembled", arg)),
+            _ => path = Some(arg)
+        }
+    }
+    let path = path.unwrap_or_else(|| fail("no ROM given"));
+
+    let rom = match rom::read(&path, platform.memory_size()) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("disasm: {}", e);
+            process::exit(1);
+        }
+    };
+    print!("{}", disassembler::disassemble(&rom, platform));
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("disasm: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
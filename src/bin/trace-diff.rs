@@ -0,0 +1,162 @@
+extern crate emulator;
+
+use std::fs::File;
+use std::{env, process};
+
+use emulator::chip8::{self, Chip8};
+use emulator::platform::Platform;
+use emulator::quirks::Quirks;
+use emulator::{rom, trace, tracediff};
+
+const USAGE: &str = "\
+Usage: trace-diff [OPTIONS] <ROM>
+
+Runs a ROM twice, as A and B, with the same keys and reports the first
+instruction after which the two runs differ in PC, registers, stack, timers,
+memory or the screen. With --reference, A is a trace written by
+`emulator --trace` instead, and only what the trace holds is compared.
+
+Exits with 0 if the runs agree, 1 if they differ.
+
+Options:
+  --platform <NAME>     Platform for both runs: chip8 (default), schip or xochip
+  --quirks <PROFILE>    Quirks for both runs: vip, chip48, schip, xochip or modern
+  --a-platform <NAME>   Platform for run A only
+  --b-platform <NAME>   Platform for run B only
+  --a-quirks <PROFILE>  Quirks for run A only
+  --b-quirks <PROFILE>  Quirks for run B only
+  --reference <TRACE>   Compare run B against a text or binary trace
+  --seed <N>            Seed for CXNN in both runs (default 0); against a
+                        trace, the one the emulator was given
+  --ips <N>             Instructions per second, which sets how fast the
+                        timers run (default 700, as in the emulator)
+  --cycles <N>          Stop after N instructions (default 1000000)
+  --context <N>         Instructions to show before the difference (default 8)
+  --key <CYCLE=KEYS>    From instruction CYCLE on hold KEYS, hex digits such
+                        as 5 or 4A; leave KEYS empty to let go. Repeatable
+  -h, --help            Print this help";
+
+#[derive(Default)]
+struct Run {
+    platform: Option<Platform>,
+    quirks: Option<Quirks>
+}
+
+fn main() {
+    let (mut a, mut b) = (Run::default(), Run::default());
+    let mut reference = None;
+    let mut seed = 0;
+    let mut ips = chip8::DEFAULT_IPS;
+    let mut cycles = 1_000_000;
+    let mut context = 8;
+    let mut inputs = Vec::new();
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            "--platform" | "--a-platform" | "--b-platform" => {
+                let platform = platform(&args.next().unwrap_or_default());
+                if arg != "--b-platform" {
+                    a.platform = Some(platform);
+                }
+                if arg != "--a-platform" {
+                    b.platform = Some(platform);
+                }
+            },
+            "--quirks" | "--a-quirks" | "--b-quirks" => {
+                let quirks = quirks(&args.next().unwrap_or_default());
+                if arg != "--b-quirks" {
+                    a.quirks = Some(quirks);
+                }
+                if arg != "--a-quirks" {
+                    b.quirks = Some(quirks);
+                }
+            },
+            "--reference" => reference = Some(args.next().unwrap_or_else(|| fail("--reference needs a trace file"))),
+            "--seed" => seed = number(&arg, args.next()) as u64,
+            "--ips" => ips = number(&arg, args.next()).max(1) as u32,
+            "--cycles" => cycles = number(&arg, args.next()) as u64,
+            "--context" => context = number(&arg, args.next()),
+            "--key" => {
+                let value = args.next().unwrap_or_default();
+                inputs.push(key(&value).unwrap_or_else(|| fail(&format!("bad --key '{}', expected CYCLE=KEYS", value))));
+            },
+            _ if arg.starts_with('-') => fail(&format!("unknown option '{}'", arg)),
+            _ if path.is_some() => fail(&format!("unexpected argument '{}', only one ROM can be run", arg)),
+            _ => path = Some(arg)
+        }
+    }
+    let path = path.unwrap_or_else(|| fail("no ROM given"));
+    inputs.sort_by_key(|&(cycle, _)| cycle);
+
+    // Read with room for the larger platform; each run checks its own limit
+    // when the ROM is loaded.
+    let rom = rom::read(&path, 0x10000).unwrap_or_else(|e| exit_with(&e.to_string()));
+    let divergence = match reference {
+        Some(trace_path) => {
+            let records = File::open(&trace_path).and_then(trace::read)
+                .unwrap_or_else(|e| exit_with(&format!("{}: {}", trace_path, e)));
+            tracediff::diff_against_trace(&mut machine(&b, &rom, seed, ips), &records, &inputs, context)
+        },
+        None => tracediff::diff_runs(&mut machine(&a, &rom, seed, ips), &mut machine(&b, &rom, seed, ips), &inputs, cycles, context)
+    };
+    match divergence {
+        Some(divergence) => {
+            print!("{}", divergence);
+            process::exit(1);
+        },
+        None => println!("no difference")
+    }
+}
+
+fn machine(run: &Run, rom: &[u8], seed: u64, ips: u32) -> Chip8 {
+    let platform = run.platform.unwrap_or_default();
+    let mut chip = chip8::init_chip();
+    chip.set_platform(platform);
+    chip.load_rom_bytes(rom).unwrap_or_else(|e| exit_with(&e.to_string()));
+    chip.set_quirks(run.quirks.unwrap_or_else(|| platform.default_quirks()));
+    chip.set_seed(seed);
+    chip.set_ips(ips);
+    chip
+}
+
+fn platform(name: &str) -> Platform {
+    Platform::from_name(name).unwrap_or_else(|| {
+        fail(&format!("unknown platform '{}', expected chip8, schip or xochip", name))
+    })
+}
+
+fn quirks(name: &str) -> Quirks {
+    Quirks::from_name(name).unwrap_or_else(|| {
+        fail(&format!("unknown quirks profile '{}', expected vip, chip48, schip, xochip or modern", name))
+    })
+}
+
+fn number(option: &str, value: Option<String>) -> usize {
+    let value = value.unwrap_or_default();
+    value.parse().unwrap_or_else(|_| fail(&format!("{} needs a number, got '{}'", option, value)))
+}
+
+// "120=4A" holds keys 4 and A from instruction 120 on.
+fn key(value: &str) -> Option<(u64, u16)> {
+    let (cycle, keys) = value.split_once('=')?;
+    let mut mask = 0;
+    for digit in keys.chars() {
+        mask |= 1 << digit.to_digit(16)?;
+    }
+    Some((cycle.parse().ok()?, mask))
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("trace-diff: {}", message);
+    process::exit(2);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("trace-diff: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,972 @@
+use std::path::Path;
+mod arithmic_opcode_tests;
+mod bitwise_opcode_tests;
+mod clock_execution_and_memory_tests;
+mod flow_control_tests;
+mod timer_tests;
+mod input_tests;
+mod graphic_tests;
+mod rom_tests;
+mod error_tests;
+mod quirks_tests;
+mod superchip_tests;
+mod xochip_tests;
+mod savestate_tests;
+
+
+use crate::audio::{AudioSink, Pattern, Sound};
+use crate::input::Input;
+use crate::display::{Display, DisplayBackend};
+use crate::rom::{self, RomError, ROM_START};
+use crate::error::{ErrorKind, ExecError, OpcodePolicy};
+use crate::instruction::{decode, Instruction};
+use crate::quirks::{MemoryIncrement, Quirks};
+use crate::platform::Platform;
+use crate::random::Random;
+use crate::savestate::{self, MachineState, SaveStateError};
+use crate::trace::{TraceRecord, Tracer};
+use crate::watch::{AccessKind, MemoryAccess};
+
+// Instructions per second unless set_ips says otherwise.
+pub const DEFAULT_IPS: u32 = 700;
+
+pub struct Chip8 {
+    program_counter: u32,  //The program counter (PC) should be 16-bit, and is used to store the currently executing address.
+    i_register: u32, // There is also a 16-bit register called I. This register is generally used to store memory addresses, so only the lowest (rightmost) 12 bits are usually used.
+    registers: [u32; 0x10], // Chip-8 has 16 general purpose 8-bit registers, usually referred to as Vx, where x is a hexadecimal digit (0 through F). There is also a 16-bit register called I. This register is generally used to store memory addresses, so only the lowest (rightmost) 12 bits are usually used.
+    sp: u32,// The stack pointer (SP) can be 8-bit, it is used to point to the topmost level of the stack.
+    delay_timer: u32, // Chip-8 also has two special purpose 8-bit registers, for the delay and sound timers. When these registers are non-zero, they are automatically decremented at a rate of 60Hz. See the section 2.5, Timers & Sound, for more information on these.
+    sound_timer: u32, // Chip-8 also has two special purpose 8-bit registers, for the delay and sound timers. When these registers are non-zero, they are automatically decremented at a rate of 60Hz. See the section 2.5, Timers & Sound, for more information on these.
+    stack: [u32; 16], // The stack is an array of 16 16-bit values, used to store the address that the interpreter shoud return to when finished with a subroutine. Chip-8 allows for up to 16 levels of nested subroutines.
+    memory: Vec<u8>, // 4 KiB, or 64 KiB on XO-CHIP.
+    ips: u32, // Instructions per emulated second; the timers tick every 1/60th of it.
+    policy: OpcodePolicy,
+    halted: Option<ExecError>,
+    quirks: Quirks,
+    drawn_this_frame: bool,
+    platform: Platform,
+    rpl: [u8; 16], // SUPER-CHIP "RPL user flags", HP-48 calculator registers that survive a reset.
+    exited: bool,
+    audio_pattern: [u8; 16], // XO-CHIP 1-bit sample loop, played while the sound timer runs.
+    pitch: u8,
+    rom_hash: u64,
+    memory_trace: Option<Vec<MemoryAccess>>, // Only recorded while a debugger watches memory.
+    cycles: u64,
+    frames: u64, // Frames ended since the last reset; the timers tick at the end of each.
+    frame_cycles: u64, // Instructions run in the current frame.
+    key_wait: Option<u8>, // The key an FX0A saw go down; it completes once the key is released.
+    tracer: Option<Tracer>,
+    audio: Option<Box<dyn AudioSink>>,
+    random: Random,
+    pub input: Input,
+    pub display: Display,
+}
+
+const FONTSET: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+// SUPER-CHIP 8x10 digits, stored right after the small font.
+const BIG_FONT_START: usize = 0x50;
+const BIG_FONTSET: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+pub fn init_chip() -> Chip8 {
+    init_chip_with_display(Display::headless())
+}
+
+pub fn init_chip_with_backend(backend: Box<dyn DisplayBackend>) -> Chip8 {
+    init_chip_with_display(Display::new(backend))
+}
+
+fn init_chip_with_display(display: Display) -> Chip8 {
+    let mut chip = Chip8 {
+        program_counter: 0x200,
+        i_register: 0,
+        registers: [0; 0x10],
+        sp: 0,
+        delay_timer: 0,
+        sound_timer: 0,
+        stack: [0; 16],
+        memory: Vec::new(),
+        ips: DEFAULT_IPS,
+        policy: OpcodePolicy::Halt,
+        halted: None,
+        quirks: Quirks::default(),
+        drawn_this_frame: false,
+        platform: Platform::default(),
+        rpl: [0; 16],
+        exited: false,
+        audio_pattern: [0; 16],
+        pitch: 64,
+        rom_hash: 0,
+        memory_trace: None,
+        cycles: 0,
+        frames: 0,
+        key_wait: None,
+        frame_cycles: 0,
+        tracer: None,
+        audio: None,
+        random: Random::new(rand::random()),
+        input: Input::new(),
+        display
+    };
+
+    chip.reset();
+    chip
+}
+
+impl Chip8 {
+    
+    pub fn cycle(&mut self) -> Result<(), ExecError> {
+        if let Some(error) = self.halted {
+            return Err(error);
+        }
+        if self.exited {
+            return Ok(());
+        }
+        if self.program_counter as usize + 1 >= self.memory.len() {
+            let error = ExecError { pc: self.program_counter, opcode: 0, kind: ErrorKind::MemoryOutOfBounds(self.program_counter + 1) };
+            self.halted = Some(error);
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.dump();
+            }
+            return Err(error);
+        }
+
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.record(self.get_trace_record());
+            self.tracer = Some(tracer);
+        }
+        self.cycles += 1;
+        let opcode_part_one = ((self.memory[self.program_counter as usize] as u32) << 8) & 0xFF00;
+
+        self.program_counter += 1;
+        let opcode_part_two = self.memory[self.program_counter as usize] as u32 & 0xFF;
+        self.program_counter += 1;
+
+        let instruction = opcode_part_one | opcode_part_two;
+        let result = self.execute(instruction);
+        // The frame that ends with this instruction ticks the timers.
+        self.frame_cycles += 1;
+        while self.frame_cycles >= self.frame_length(self.frames) {
+            self.frame_cycles -= self.frame_length(self.frames);
+            self.frames += 1;
+            self.countdown_timers();
+        }
+        if result.is_err() {
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.dump();
+            }
+        }
+        result
+    }
+
+    // Packs a graphics row (8 pixels of the sprite) into a byte
+    pub fn get_sprite_row(&mut self, x: u32, y: u32, _video: [u8; 64 * 32]) -> u8 {
+        let mut row = 0u8;
+        for i in 0..8 {
+            row |= (self.display.get_pixel((x + i) as usize, y as usize) & 1) << (7 - i);
+        }
+        row
+    }
+
+    // The low resolution screen, one byte per pixel, row by row.
+    pub fn get_screen(&mut self) -> [u8; 64 * 32] {
+        let mut screen = [0; 64 * 32];
+        for (y, line) in self.display.get_gfx().iter().take(32).enumerate() {
+            screen[y * 64..(y + 1) * 64].copy_from_slice(&line[..64]);
+        }
+        screen
+    }
+
+    // The instructions in a frame. A second's worth are spread evenly over its
+    // 60 frames, so frame k of a second ends after ips * (k + 1) / 60 of them,
+    // rounded down.
+    fn frame_length(&self, frame: u64) -> u64 {
+        let ips = self.ips as u64;
+        ips * (frame % 60 + 1) / 60 - ips * (frame % 60) / 60
+    }
+
+    // How many instructions are left before the timers next tick. A frontend
+    // runs this many per frame it shows, which keeps its frames in step with
+    // the machine's even after a state is loaded or the speed changes.
+    pub fn cycles_left_in_frame(&self) -> u64 {
+        self.frame_length(self.frames).saturating_sub(self.frame_cycles).max(1)
+    }
+
+    fn countdown_timers(&mut self) {
+        self.drawn_this_frame = false;
+        if self.audio.is_some() {
+            // A pattern of all zeros is taken as none loaded, which is
+            // how every non-XO-CHIP program leaves it.
+            let pattern = match self.audio_pattern {
+                bits if bits == [0; 16] => None,
+                bits => Some(Pattern { bits, rate: self.get_playback_rate() })
+            };
+            let sound = Sound { sounding: self.sound_timer > 0, pattern };
+            if let Some(audio) = self.audio.as_mut() {
+                audio.frame(sound);
+            }
+        }
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    pub fn get_i_register(&self) -> &u32 { 
+        &self.i_register
+    }
+
+    pub fn get_sp(&self) -> &u32 {
+        &self.sp
+    }
+
+    pub fn get_stack(&self) -> &[u32; 16] {
+        &self.stack
+    }
+
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn get_delay_timer(&self) -> u32 {
+        self.delay_timer
+    }
+
+    pub fn get_sound_timer(&self) -> u32 {
+        self.sound_timer
+    }
+
+    pub fn get_register(&self, x: usize) -> u32 {
+        self.get_vx(x)
+    }
+
+    // Puts the machine back in its power-on state: registers, timers, stack and
+    // screen cleared, memory wiped apart from the font. Key state is left alone
+    // since it mirrors the host keyboard.
+    pub fn reset(&mut self) {
+        self.program_counter = ROM_START as u32;
+        self.i_register = 0;
+        self.registers = [0; 0x10];
+        self.sp = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.stack = [0; 16];
+        self.memory = vec![0; self.platform.memory_size()];
+        self.memory[..FONTSET.len()].copy_from_slice(&FONTSET);
+        self.memory[BIG_FONT_START..BIG_FONT_START + BIG_FONTSET.len()].copy_from_slice(&BIG_FONTSET);
+        self.halted = None;
+        self.drawn_this_frame = false;
+        self.exited = false;
+        self.cycles = 0;
+        self.frames = 0;
+        self.frame_cycles = 0;
+        self.key_wait = None;
+        self.random.reseed(self.random.get_seed());
+        self.audio_pattern = [0; 16];
+        self.pitch = 64;
+        self.display.set_planes(1);
+        self.display.set_hires(false);
+    }
+
+    // Copies a ROM to 0x200 and returns its size in bytes. Memory outside the
+    // ROM is left as it was; use reload_rom to start from a clean machine.
+    pub fn load_rom<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, RomError> {
+        let contents = rom::read(path, self.memory.len())?;
+        self.load_rom_bytes(&contents)
+    }
+
+    pub fn load_rom_bytes(&mut self, contents: &[u8]) -> Result<usize, RomError> {
+        rom::validate(contents, self.memory.len())?;
+        self.memory[ROM_START..ROM_START + contents.len()].copy_from_slice(contents);
+        self.rom_hash = savestate::rom_hash(contents);
+        Ok(contents.len())
+    }
+
+    pub fn reload_rom<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, RomError> {
+        let contents = rom::read(path, self.memory.len())?;
+        self.reload_rom_bytes(&contents)
+    }
+
+    pub fn reload_rom_bytes(&mut self, contents: &[u8]) -> Result<usize, RomError> {
+        rom::validate(contents, self.memory.len())?;
+        self.reset();
+        self.load_rom_bytes(contents)
+    }
+
+    // Restarts CXNN's sequence of random numbers. Machines start with a seed
+    // of their own, so set one to make a run repeatable.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random.reseed(seed);
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.random.get_seed()
+    }
+
+    pub fn get_pc(&self) -> u32 { 
+        self.program_counter 
+    }
+
+    fn set_vx(&mut self, value: u32, register: usize) {
+        self.registers[register] = 0x000000FF & value;
+    }
+
+    fn get_vx(&self, x: usize) -> u32 { 
+        (0x000000FF & self.registers[x])
+    }
+
+    pub fn get_v0(&self) -> u32 { 
+        (self.registers[0] & 0xFF)
+    }
+    
+    pub fn get_v1(&self) -> u32 { 
+        (self.registers[1] & 0xFF)
+    }
+
+    pub fn get_v2(&self) -> u32 { 
+        (self.registers[0x2] & 0xFF)
+    }
+
+    pub fn get_v3(&self) -> u32 { 
+        (self.registers[0x3] & 0xFF)
+    }
+
+    pub fn get_v4(&self) -> u32 { 
+        (self.registers[0x4] & 0xFF)
+    }
+
+    pub fn get_v5(&self) -> u32 { 
+        (self.registers[0x5] & 0xFF)
+    }
+
+    pub fn get_v6(&self) -> u32 { 
+        (self.registers[0x6] & 0xFF)
+    }
+
+    pub fn get_v7(&self) -> u32 { 
+        (self.registers[0x7] & 0xFF)
+    }
+    
+    pub fn get_v8(&self) -> u32 { 
+        (self.registers[0x8] & 0xFF)
+    }
+
+    pub fn get_v9(&self) -> u32 { 
+        (self.registers[0x9] & 0xFF)
+    }
+
+    pub fn get_va(&self) -> u32 { 
+        (self.registers[0xa] & 0xFF)
+    }
+
+    pub fn get_vb(&self) -> u32 { 
+        (self.registers[0xb] & 0xFF)
+    }
+
+    pub fn get_vc(&self) -> u32 { 
+        (self.registers[0xc] & 0xFF)
+    }
+
+    pub fn get_vd(&self) -> u32 { 
+        (self.registers[0xd] & 0xFF)
+    }
+
+    pub fn get_ve(&self) -> u32 { 
+        (self.registers[0xe] & 0xFF)
+    }
+
+    pub fn get_vf(&self) -> u32 { 
+        (self.registers[0xf] & 0xFF)
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    // Memory grows or shrinks to the size of the new platform, so this is best
+    // called before loading a ROM.
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+        self.memory.resize(platform.memory_size(), 0);
+    }
+
+    pub fn get_platform(&self) -> Platform {
+        self.platform
+    }
+
+    // Set once a SUPER-CHIP program runs 00FD; cycle does nothing afterwards.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn set_opcode_policy(&mut self, policy: OpcodePolicy) {
+        self.policy = policy;
+    }
+
+    pub fn get_opcode_policy(&self) -> OpcodePolicy {
+        self.policy
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.is_some()
+    }
+
+    // Instructions fetched since the last reset.
+    pub fn get_cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // The timers count down once every ips / 60 instructions rather than by
+    // the wall clock, so a run behaves the same however fast the host is. A
+    // change takes effect from the current frame on: instructions already run
+    // in it still count, and if they are now enough the frame ends with the
+    // next one.
+    pub fn set_ips(&mut self, ips: u32) {
+        self.ips = ips.max(1);
+        self.frame_cycles = self.frame_cycles.min(self.frame_length(self.frames).saturating_sub(1));
+    }
+
+    pub fn get_ips(&self) -> u32 {
+        self.ips
+    }
+
+    // Identifies the last ROM loaded; save states are tied to it.
+    pub fn get_rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    pub fn snapshot(&self) -> MachineState {
+        let mut registers = [0; 16];
+        for (i, register) in registers.iter_mut().enumerate() {
+            *register = self.registers[i] as u8;
+        }
+        let mut stack = [0; 16];
+        for (i, address) in stack.iter_mut().enumerate() {
+            *address = self.stack[i] as u16;
+        }
+        MachineState {
+            platform: self.platform,
+            program_counter: self.program_counter as u16,
+            i_register: self.i_register as u16,
+            registers,
+            sp: self.sp as u8,
+            delay_timer: self.delay_timer as u8,
+            sound_timer: self.sound_timer as u8,
+            stack,
+            memory: self.memory.clone(),
+            rpl: self.rpl,
+            exited: self.exited,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            hires: self.display.is_hires(),
+            planes: self.display.get_planes(),
+            gfx: *self.display.get_gfx(),
+            keys: self.input.get_mask(),
+            key_wait: self.key_wait,
+            cycles: self.cycles,
+            frames: self.frames,
+            frame_cycles: self.frame_cycles,
+            random: self.random.snapshot()
+        }
+    }
+
+    // Any pending fault is dropped: the machine continues from the restored
+    // state as if it had just been saved. The keypad is left alone, as in
+    // reset, so keys held now stay held and no key from the state sticks.
+    // The state's keys are there for whoever replays it.
+    pub fn restore(&mut self, state: &MachineState) {
+        self.platform = state.platform;
+        self.program_counter = state.program_counter as u32;
+        self.i_register = state.i_register as u32;
+        for (i, &register) in state.registers.iter().enumerate() {
+            self.registers[i] = register as u32;
+        }
+        self.sp = state.sp as u32;
+        self.delay_timer = state.delay_timer as u32;
+        self.sound_timer = state.sound_timer as u32;
+        for (i, &address) in state.stack.iter().enumerate() {
+            self.stack[i] = address as u32;
+        }
+        self.memory = state.memory.clone();
+        self.rpl = state.rpl;
+        self.exited = state.exited;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.display.restore(&state.gfx, state.hires, state.planes);
+        self.cycles = state.cycles;
+        self.frames = state.frames;
+        // Clamped like set_ips does, for states saved at another speed or
+        // damaged ones; a frame never holds more than its length.
+        self.frame_cycles = state.frame_cycles.min(self.frame_length(self.frames).saturating_sub(1));
+        self.key_wait = state.key_wait;
+        self.random.restore(&state.random);
+        self.halted = None;
+        self.drawn_this_frame = false;
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.snapshot().encode(self.rom_hash)
+    }
+
+    // Fails without touching the machine if the state is corrupt or was saved
+    // with a different ROM.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), SaveStateError> {
+        let state = MachineState::decode(bytes, self.rom_hash)?;
+        self.restore(&state);
+        Ok(())
+    }
+
+    // Starts or stops recording the data accesses instructions make.
+    pub fn set_memory_tracing(&mut self, on: bool) {
+        if on != self.memory_trace.is_some() {
+            self.memory_trace = if on { Some(Vec::new()) } else { None };
+        }
+    }
+
+    // The accesses recorded since the last call, oldest first.
+    pub fn take_memory_accesses(&mut self) -> Vec<MemoryAccess> {
+        self.memory_trace.as_mut().map(|trace| trace.split_off(0)).unwrap_or_default()
+    }
+
+    // Starts writing a record for every instruction to `tracer`.
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    // Stops tracing and hands the tracer back, e.g. to finish it.
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    // Sends what was played, buzzer or XO-CHIP pattern, to `audio` at the end
+    // of every frame.
+    pub fn set_audio_sink(&mut self, audio: Box<dyn AudioSink>) {
+        self.audio = Some(audio);
+    }
+
+    pub fn take_audio_sink(&mut self) -> Option<Box<dyn AudioSink>> {
+        self.audio.take()
+    }
+
+    // The machine as the tracer sees it, just before the instruction at PC.
+    pub fn get_trace_record(&self) -> TraceRecord {
+        let pc = self.program_counter as usize;
+        let opcode = match self.memory.get(pc..pc + 2) {
+            Some(word) => ((word[0] as u16) << 8) | word[1] as u16,
+            None => 0
+        };
+        let mut registers = [0; 16];
+        for (register, &value) in registers.iter_mut().zip(self.registers.iter()) {
+            *register = value as u8;
+        }
+        TraceRecord {
+            cycle: self.cycles,
+            pc: pc as u16,
+            opcode,
+            registers,
+            i: self.i_register as u16,
+            sp: self.sp as u8,
+            delay_timer: self.delay_timer as u8,
+            sound_timer: self.sound_timer as u8
+        }
+    }
+
+    fn trace_memory(&mut self, kind: AccessKind, address: u32, len: u32) {
+        if let Some(trace) = self.memory_trace.as_mut() {
+            trace.push(MemoryAccess { kind, address, len });
+        }
+    }
+
+    pub fn get_audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    pub fn get_pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    // The rate in Hz at which the bits of the audio pattern are played. Pitch 64
+    // is 4000 Hz and every 48 steps up or down doubles or halves it.
+    pub fn get_playback_rate(&self) -> f64 {
+        4000.0 * 2f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+
+    // Raises a fault for the instruction that was just fetched. Unknown opcodes
+    // are dropped under the Skip policy; any other fault halts unless trapping.
+    fn fault(&mut self, instruction: u32, kind: ErrorKind) -> Result<(), ExecError> {
+        let error = ExecError { pc: self.program_counter.wrapping_sub(2), opcode: instruction, kind };
+        match self.policy {
+            OpcodePolicy::Skip if kind == ErrorKind::UnknownOpcode => Ok(()),
+            OpcodePolicy::Trap => {
+                self.program_counter = error.pc;
+                Err(error)
+            },
+            _ => {
+                self.halted = Some(error);
+                Err(error)
+            }
+        }
+    }
+
+    fn check_memory(&mut self, instruction: u32, from: u32, len: u32) -> Result<(), ExecError> {
+        let end = from + len;
+        if end as usize > self.memory.len() {
+            return self.fault(instruction, ErrorKind::MemoryOutOfBounds(end - 1));
+        }
+        Ok(())
+    }
+
+    // Skips the next instruction. On XO-CHIP that is four bytes long when it
+    // is F000 NNNN.
+    fn skip_next(&mut self) {
+        let pc = self.program_counter as usize;
+        let long = self.platform >= Platform::XoChip && pc + 1 < self.memory.len()
+            && self.memory[pc] == 0xF0 && self.memory[pc + 1] == 0x00;
+        self.program_counter += if long { 4 } else { 2 };
+    }
+
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.set_vx(0, 0xF);
+        }
+    }
+
+    fn shift_source(&self, x: u8, y: u8) -> u32 {
+        if self.quirks.shift_uses_vy { self.get_vx(y as usize) } else { self.get_vx(x as usize) }
+    }
+
+    fn increment_i_after_memory(&mut self, x: u8) {
+        match self.quirks.memory_increment {
+            MemoryIncrement::None => {},
+            MemoryIncrement::X => self.i_register += x as u32,
+            MemoryIncrement::XPlusOne => self.i_register += x as u32 + 1
+        }
+    }
+
+    pub fn execute(&mut self, instruction: u32) -> Result<(), ExecError> {
+        match decode((instruction & 0xFFFF) as u16) {
+            Ok(decoded) if decoded.platform() <= self.platform => self.execute_instruction(decoded, instruction),
+            _ => self.fault(instruction, ErrorKind::UnknownOpcode)
+        }
+    }
+
+    fn execute_instruction(&mut self, decoded: Instruction, instruction: u32) -> Result<(), ExecError> {
+        match decoded {
+            Instruction::Clear => {
+                self.display.clear();
+            },
+            Instruction::Return => {
+                if self.sp == 0 {
+                    return self.fault(instruction, ErrorKind::StackUnderflow);
+                }
+                self.sp -= 1;
+                self.program_counter = self.stack[self.sp as usize];
+            },
+            Instruction::ScrollDown { n } => {
+                self.display.scroll_down(n as usize);
+            },
+            Instruction::ScrollUp { n } => {
+                self.display.scroll_up(n as usize);
+            },
+            Instruction::ScrollRight => {
+                self.display.scroll_right(4);
+            },
+            Instruction::ScrollLeft => {
+                self.display.scroll_left(4);
+            },
+            Instruction::Exit => {
+                self.exited = true;
+            },
+            Instruction::LowRes => {
+                self.display.set_hires(false);
+            },
+            Instruction::HighRes => {
+                self.display.set_hires(true);
+            },
+            Instruction::Jump { nnn } => {
+                self.program_counter = nnn as u32;
+            },
+            Instruction::Call { nnn } => {
+                if self.sp as usize >= self.stack.len() {
+                    return self.fault(instruction, ErrorKind::StackOverflow);
+                }
+                self.stack[self.sp as usize] = self.program_counter;
+                self.sp += 1;
+                self.program_counter = nnn as u32;
+            },
+            Instruction::SkipEqImm { x, nn } => { //Skip the following instruction if the value of register VX is equal to NN
+                if self.get_vx(x as usize) == nn as u32 {
+                    self.skip_next();
+                }
+            },
+            Instruction::SkipNeImm { x, nn } => { //Skip the following instruction if the value of register VX is not equal to NN
+                if self.get_vx(x as usize) != nn as u32 {
+                    self.skip_next();
+                }
+            },
+            Instruction::SkipEqReg { x, y } => { //Skip the following instruction if the value of register VX is equal to the value of register VY
+                if self.get_vx(x as usize) == self.get_vx(y as usize) {
+                    self.skip_next();
+                }
+            },
+            // Both save and load walk from VX to VY, backwards if X is greater.
+            Instruction::StoreRange { x, y } => {
+                let count = (x as i32 - y as i32).unsigned_abs() + 1;
+                self.check_memory(instruction, self.i_register, count)?;
+                self.trace_memory(AccessKind::Write, self.i_register, count);
+                for (offset, register) in register_range(x, y).enumerate() {
+                    self.memory[self.i_register as usize + offset] = self.get_vx(register) as u8;
+                }
+            },
+            Instruction::LoadRange { x, y } => {
+                let count = (x as i32 - y as i32).unsigned_abs() + 1;
+                self.check_memory(instruction, self.i_register, count)?;
+                self.trace_memory(AccessKind::Read, self.i_register, count);
+                for (offset, register) in register_range(x, y).enumerate() {
+                    self.set_vx(self.memory[self.i_register as usize + offset] as u32, register);
+                }
+            },
+            Instruction::LoadImm { x, nn } => { //6XNN	Store number NN in register VX
+                self.set_vx(nn as u32, x as usize);
+            },
+            Instruction::AddImm { x, nn } => { //7XNN	Add number NN to register VX
+                self.set_vx(nn as u32 + self.get_vx(x as usize), x as usize);
+            },
+            Instruction::LoadReg { x, y } => {
+                self.set_vx(self.get_vx(y as usize), x as usize);
+            },
+            Instruction::Or { x, y } => {
+                self.set_vx(self.get_vx(y as usize) | self.get_vx(x as usize), x as usize);
+                self.reset_vf();
+            },
+            Instruction::And { x, y } => {
+                self.set_vx(self.get_vx(y as usize) & self.get_vx(x as usize), x as usize);
+                self.reset_vf();
+            },
+            Instruction::Xor { x, y } => {
+                self.set_vx(self.get_vx(y as usize) ^ self.get_vx(x as usize), x as usize);
+                self.reset_vf();
+            },
+            // The flag is written after the result so it survives when X is F.
+            Instruction::AddReg { x, y } => {
+                let result = self.get_vx(x as usize) + self.get_vx(y as usize);
+                self.set_vx(result, x as usize);
+                self.set_vx(if result > 0xFF { 1 } else { 0 }, 0xF);
+            },
+            Instruction::SubReg { x, y } => {
+                let v_x = self.get_vx(x as usize);
+                let v_y = self.get_vx(y as usize);
+                self.set_vx(v_x.wrapping_sub(v_y), x as usize);
+                self.set_vx(if v_x >= v_y { 1 } else { 0 }, 0xF);
+            },
+            Instruction::ShiftRight { x, y } => {
+                let v_x = self.shift_source(x, y);
+                self.set_vx(v_x >> 1, x as usize);
+                self.set_vx(v_x & 0x01, 0xF);
+            },
+            Instruction::SubNeg { x, y } => {
+                let v_x = self.get_vx(x as usize);
+                let v_y = self.get_vx(y as usize);
+                self.set_vx(v_y.wrapping_sub(v_x), x as usize);
+                self.set_vx(if v_y >= v_x { 1 } else { 0 }, 0xF);
+            },
+            Instruction::ShiftLeft { x, y } => {
+                let v_x = self.shift_source(x, y);
+                self.set_vx(v_x << 1, x as usize);
+                self.set_vx((v_x >> 7) & 0x01, 0xF);
+            },
+            Instruction::SkipNeReg { x, y } => {
+                if self.get_vx(x as usize) != self.get_vx(y as usize) {
+                    self.skip_next();
+                }
+            },
+            Instruction::LoadI { nnn } => {
+                self.i_register = nnn as u32;
+            },
+            Instruction::JumpOffset { nnn } => {
+                let offset = if self.quirks.jump_uses_vx { self.get_vx((nnn >> 8) as usize) } else { self.get_v0() };
+                self.program_counter = (nnn as u32).wrapping_add(offset);
+            },
+            Instruction::Random { x, nn } => {
+                let rand = self.random.next_byte();
+                self.set_vx((rand & nn) as u32, x as usize);
+            },
+            // Draw a sprite at position VX, VY with N bytes of sprite data starting at the address stored in I
+            // Set VF to 01 if any set pixels are changed to unset, and 00 otherwise
+            Instruction::Draw { x, y, n } => {
+                if self.quirks.display_wait && self.drawn_this_frame {
+                    self.program_counter -= 2;
+                    return Ok(());
+                }
+                self.drawn_this_frame = true;
+                let x = self.get_vx(x as usize) as usize;
+                let y = self.get_vx(y as usize) as usize;
+                let clip = self.quirks.clip_sprites;
+                let from = self.i_register as usize;
+
+                let planes = self.display.plane_count();
+
+                // DXY0 draws a 16x16 sprite of 32 bytes on SUPER-CHIP.
+                let val = if n == 0 && self.platform >= Platform::SuperChip {
+                    let len = 32 * planes;
+                    self.check_memory(instruction, self.i_register, len as u32)?;
+                    self.trace_memory(AccessKind::Read, self.i_register, len as u32);
+                    self.display.draw_wide(x, y, &self.memory[from..from + len], clip)
+                } else {
+                    let len = n as usize * planes;
+                    self.check_memory(instruction, self.i_register, len as u32)?;
+                    self.trace_memory(AccessKind::Read, self.i_register, len as u32);
+                    self.display.draw(x, y, &self.memory[from..from + len], clip)
+                };
+                self.set_vx(val as u32, 0xf);
+            },
+            Instruction::SkipKey { x } => {
+                if self.input.pressed((self.get_vx(x as usize) & 0xF) as usize) {
+                    self.skip_next();
+                }
+            },
+            Instruction::SkipNotKey { x } => {
+                if !self.input.pressed((self.get_vx(x as usize) & 0xF) as usize) {
+                    self.skip_next();
+                }
+            },
+            Instruction::LoadILong => {
+                self.check_memory(instruction, self.program_counter, 2)?;
+                let pc = self.program_counter as usize;
+                self.i_register = ((self.memory[pc] as u32) << 8) | self.memory[pc + 1] as u32;
+                self.program_counter += 2;
+            },
+            Instruction::SelectPlanes { n } => {
+                self.display.set_planes(n);
+            },
+            Instruction::LoadAudio => {
+                self.check_memory(instruction, self.i_register, 16)?;
+                self.trace_memory(AccessKind::Read, self.i_register, 16);
+                let i = self.i_register as usize;
+                self.audio_pattern.copy_from_slice(&self.memory[i..i + 16]);
+            },
+            Instruction::LoadDelay { x } => {
+                self.set_vx(self.delay_timer, x as usize);
+            },
+            // Runs again until a key is down, then stores the lowest one held.
+            // As on the VIP, a key counts once it has been pressed and
+            // released again, so a held key does not run through several
+            // FX0As in a row.
+            Instruction::WaitKey { x } => {
+                match self.key_wait {
+                    Some(key) if !self.input.pressed(key as usize) => {
+                        self.key_wait = None;
+                        self.set_vx(key as u32, x as usize);
+                    },
+                    Some(_) => self.program_counter -= 0x2,
+                    None => {
+                        self.key_wait = (0..16).find(|&key| self.input.pressed(key)).map(|key| key as u8);
+                        self.program_counter -= 0x2;
+                    }
+                }
+            },
+            Instruction::SetDelay { x } => {
+                self.delay_timer = self.get_vx(x as usize);
+            },
+            Instruction::SetSound { x } => {
+                self.sound_timer = self.get_vx(x as usize);
+            },
+            Instruction::AddI { x } => {
+                self.i_register += self.get_vx(x as usize);
+            },
+            Instruction::LoadFont { x } => {
+                self.i_register = (self.get_vx(x as usize) & 0xF) * 5;
+            },
+            Instruction::LoadBigFont { x } => {
+                self.i_register = BIG_FONT_START as u32 + (self.get_vx(x as usize) & 0xF) * 10;
+            },
+            Instruction::SetPitch { x } => {
+                self.pitch = self.get_vx(x as usize) as u8;
+            },
+            Instruction::StoreBcd { x } => {
+                self.check_memory(instruction, self.i_register, 3)?;
+                self.trace_memory(AccessKind::Write, self.i_register, 3);
+                let value = self.get_vx(x as usize);
+                let i = self.i_register as usize;
+                self.memory[i] = (value / 100) as u8;
+                self.memory[i + 1] = ((value % 100) / 10) as u8;
+                self.memory[i + 2] = (value % 10) as u8;
+            },
+            Instruction::StoreRegs { x } => {
+                self.check_memory(instruction, self.i_register, x as u32 + 1)?;
+                self.trace_memory(AccessKind::Write, self.i_register, x as u32 + 1);
+                for i in 0..=x as u32 {
+                    self.memory[(self.i_register + i) as usize] = self.get_vx(i as usize) as u8;
+                }
+                self.increment_i_after_memory(x);
+            },
+            Instruction::LoadRegs { x } => {
+                self.check_memory(instruction, self.i_register, x as u32 + 1)?;
+                self.trace_memory(AccessKind::Read, self.i_register, x as u32 + 1);
+                for i in 0..=x as u32 {
+                    self.set_vx(self.memory[(self.i_register + i) as usize] as u32, i as usize);
+                }
+                self.increment_i_after_memory(x);
+            },
+            Instruction::StoreFlags { x } => {
+                for i in 0..=x as usize {
+                    self.rpl[i] = self.get_vx(i) as u8;
+                }
+            },
+            Instruction::LoadFlags { x } => {
+                for i in 0..=x as usize {
+                    self.set_vx(self.rpl[i] as u32, i);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = usize>> {
+    if x <= y {
+        Box::new(x as usize..=y as usize)
+    } else {
+        Box::new((y as usize..=x as usize).rev())
+    }
+}
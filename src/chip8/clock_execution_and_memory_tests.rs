@@ -4,7 +4,7 @@ mod clock_execution_and_memory_tests {
 
     fn set_up_load_rom() -> Chip8 {
         let mut chip = init_chip();
-        chip.load_rom(std::string::String::from("E03TestRom.ch8"));
+        chip.load_rom("E03TestRom.ch8").unwrap();
         chip
     }
 
@@ -12,10 +12,10 @@ mod clock_execution_and_memory_tests {
     #[test]
     fn test_cycle() {
         let mut chip8 = set_up_load_rom();
-        chip8.cycle();
-        chip8.cycle();
-        chip8.cycle();
-        chip8.cycle();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
         assert_eq!(0x15,    chip8.get_v0());
         assert_eq!(0x20,    chip8.get_v1());
         assert_eq!(0x25,    chip8.get_v2());
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod error_tests {
+    use crate::chip8::*;
+    use crate::error::{ErrorKind, ExecError, OpcodePolicy};
+
+    fn set_up(rom: &[u8], policy: OpcodePolicy) -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(rom).unwrap();
+        chip.set_opcode_policy(policy);
+        chip
+    }
+
+    #[test]
+    fn test_unknown_opcode_halts() {
+        let mut chip8 = set_up(&[0x60, 0x01, 0x80, 0x1F, 0x61, 0x02], OpcodePolicy::Halt);
+        chip8.cycle().unwrap();
+
+        let expected = ExecError { pc: 0x202, opcode: 0x801F, kind: ErrorKind::UnknownOpcode };
+        assert_eq!(Err(expected), chip8.cycle());
+        assert!(chip8.is_halted());
+
+        // A halted machine stays halted and does not execute anything else.
+        assert_eq!(Err(expected), chip8.cycle());
+        assert_eq!(0, chip8.get_v1());
+    }
+
+    #[test]
+    fn test_unknown_opcode_skipped() {
+        let mut chip8 = set_up(&[0xE0, 0x00, 0xF0, 0xFF, 0x01, 0x23, 0x61, 0x02], OpcodePolicy::Skip);
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        assert_eq!(0x2, chip8.get_v1());
+        assert_eq!(0x208, chip8.get_pc());
+    }
+
+    #[test]
+    fn test_unknown_opcode_trapped() {
+        let mut chip8 = set_up(&[0x60, 0x01, 0xE0, 0x00], OpcodePolicy::Trap);
+        chip8.cycle().unwrap();
+
+        let error = chip8.cycle().unwrap_err();
+        assert_eq!(0x202, error.pc);
+        assert_eq!(0xE000, error.opcode);
+        assert_eq!(0x202, chip8.get_pc());
+        assert!(!chip8.is_halted());
+    }
+
+    #[test]
+    fn test_stack_faults() {
+        let mut chip8 = set_up(&[0x00, 0xEE], OpcodePolicy::Skip);
+        assert_eq!(ErrorKind::StackUnderflow, chip8.cycle().unwrap_err().kind);
+
+        // 0x200 calls itself until the 16 levels of stack run out.
+        let mut chip8 = set_up(&[0x22, 0x00], OpcodePolicy::Halt);
+        for _ in 0..16 {
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(ErrorKind::StackOverflow, chip8.cycle().unwrap_err().kind);
+    }
+
+    #[test]
+    fn test_memory_out_of_bounds() {
+        let mut chip8 = set_up(&[0xAF, 0xFE, 0xF2, 0x55], OpcodePolicy::Halt);
+        chip8.cycle().unwrap();
+        assert_eq!(ErrorKind::MemoryOutOfBounds(0x1000), chip8.cycle().unwrap_err().kind);
+
+        let mut chip8 = set_up(&[0x1F, 0xFF], OpcodePolicy::Halt);
+        chip8.cycle().unwrap();
+        assert_eq!(ErrorKind::MemoryOutOfBounds(0x1000), chip8.cycle().unwrap_err().kind);
+    }
+
+    #[test]
+    fn test_reset_clears_halt() {
+        let mut chip8 = set_up(&[0x00, 0x00, 0xF0, 0xFF], OpcodePolicy::Halt);
+        chip8.cycle().unwrap();
+        assert!(chip8.cycle().is_err());
+        chip8.reset();
+        assert!(!chip8.is_halted());
+    }
+}
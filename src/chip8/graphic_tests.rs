@@ -4,17 +4,17 @@ mod graphic_tests {
 
     fn set_up() -> Chip8 {
         let mut chip = init_chip();
-        chip.load_rom(std::string::String::from("E07GraphicsRom.ch8"));
-        chip.execute(0x6064);
-        chip.execute(0x6127);
-        chip.execute(0x6212);
-        chip.execute(0x63AE);
-        chip.execute(0x64FF);
-        chip.execute(0x65B4);
-        chip.execute(0x6642);
-        chip.execute(0x673F);
-        chip.execute(0x681F);
-        chip.execute(0x6F25);
+        chip.load_rom("E07GraphicsRom.ch8").unwrap();
+        chip.execute(0x6064).unwrap();
+        chip.execute(0x6127).unwrap();
+        chip.execute(0x6212).unwrap();
+        chip.execute(0x63AE).unwrap();
+        chip.execute(0x64FF).unwrap();
+        chip.execute(0x65B4).unwrap();
+        chip.execute(0x6642).unwrap();
+        chip.execute(0x673F).unwrap();
+        chip.execute(0x681F).unwrap();
+        chip.execute(0x6F25).unwrap();
         chip
     }
 
@@ -29,10 +29,10 @@ mod graphic_tests {
     #[test]
     fn test_i_register() {
         let mut chip = set_up();
-        chip.execute(0xA123);
+        chip.execute(0xA123).unwrap();
         assert_eq!(0x123, *chip.get_i_register());
 
-        chip.execute(0xF11E);
+        chip.execute(0xF11E).unwrap();
         assert_eq!(0x123 + 0x27, *chip.get_i_register());
     }
 
@@ -55,8 +55,8 @@ mod graphic_tests {
     #[test]
     fn draw_sprite() {
         let mut chip = set_up();
-        chip.execute(0xA202);
-        chip.execute(0xD122); // Draw Sprite at 39, 18
+        chip.execute(0xA202).unwrap();
+        chip.execute(0xD122).unwrap(); // Draw Sprite at 39, 18
         assert_eq!(0, chip.get_vf());
 
         let mut video = chip.get_screen();
@@ -78,7 +78,7 @@ mod graphic_tests {
         assert_eq!(0, video[45 + 64 * 19]);
         assert_eq!(0, video[46 + 64 * 19]);
 
-        chip.execute(0xD122); // Draw Sprite at 39,18
+        chip.execute(0xD122).unwrap(); // Draw Sprite at 39,18
         assert_eq!(1, chip.get_vf());
 
         video = chip.get_screen();
@@ -109,8 +109,8 @@ mod graphic_tests {
     #[test]
     fn draw_sprite_wrap() {
         let mut chip = set_up();
-        chip.execute(0xA202);
-        chip.execute(0xD212); // Draw Sprite at 18, 7 (39 wraps to 7)
+        chip.execute(0xA202).unwrap();
+        chip.execute(0xD212).unwrap(); // Draw Sprite at 18, 7 (39 wraps to 7)
         assert_eq!(0, chip.get_vf());
 
         let mut video = chip.get_screen();
@@ -141,11 +141,11 @@ mod graphic_tests {
     #[test]
     fn draw_sprite_bottom_right_edge() {
         let mut chip = set_up();
-        chip.execute(0xA202);
-        chip.execute(0xD781); 
-        chip.execute(0xD871); 
-        chip.execute(0xD781); 
-        chip.execute(0xD871); 
+        chip.execute(0xA202).unwrap();
+        chip.execute(0xD781).unwrap(); 
+        chip.execute(0xD871).unwrap(); 
+        chip.execute(0xD781).unwrap(); 
+        chip.execute(0xD871).unwrap(); 
     }
     
     /**
@@ -155,9 +155,9 @@ mod graphic_tests {
     #[test]
     fn test_clear_video() {
         let mut chip = set_up();
-        chip.execute(0xA202);
-        chip.execute(0xD212); // Draw Sprite at 18, 7 (39 wraps to 7)
-        chip.execute(0x00E0);
+        chip.execute(0xA202).unwrap();
+        chip.execute(0xD212).unwrap(); // Draw Sprite at 18, 7 (39 wraps to 7)
+        chip.execute(0x00E0).unwrap();
 
         let mut video = chip.get_screen();
 
@@ -183,23 +183,22 @@ mod graphic_tests {
     fn test_hex_fonts() {
         let mut chip = set_up();
 
-        chip.execute(0x6100); //Store 00 in V1
-        chip.execute(0x6200); //Store 00 in V2
+        chip.execute(0x6100).unwrap(); //Store 00 in V1
+        chip.execute(0x6200).unwrap(); //Store 00 in V2
 
         for i in 0..0xf {
-            chip.execute(0x6000 + i); //Store 00 in V0
-            chip.execute(0xF029); // Set I to the memory address of the font sprite in V0
-            chip.execute(0xD125); // Draw all 5 lines of the sprite at 0, 0.
+            chip.execute(0x6000 + i).unwrap(); //Store 00 in V0
+            chip.execute(0xF029).unwrap(); // Set I to the memory address of the font sprite in V0
+            chip.execute(0xD125).unwrap(); // Draw all 5 lines of the sprite at 0, 0.
 
-            check_graphics(i);//Test for being drawn.
-            chip.execute(0x00E0); // Clear Screen
+            check_graphics(&mut chip, i);//Test for being drawn.
+            chip.execute(0x00E0).unwrap(); // Clear Screen
         }
     }
 
     
-    fn check_graphics(i: u32) {
-        let mut chip = set_up();
-        let mut video = chip.get_screen();
+    fn check_graphics(chip: &mut Chip8, i: u32) {
+        let video = chip.get_screen();
         match i {
             0 => {
                 assert_eq!(0xF0, chip.get_sprite_row(0, 0, video));
@@ -4,15 +4,15 @@ mod input_tests {
 
     fn set_up() -> Chip8 {
         let mut chip = init_chip();
-        chip.load_rom(std::string::String::from("E06KeypadLoop.ch8"));
-        chip.execute(0x6064);
-        chip.execute(0x6127);
-        chip.execute(0x6212);
-        chip.execute(0x63AE);
-        chip.execute(0x64FF);
-        chip.execute(0x65B4);
-        chip.execute(0x6642);
-        chip.execute(0x6F25);
+        chip.load_rom("E06KeypadLoop.ch8").unwrap();
+        chip.execute(0x6064).unwrap();
+        chip.execute(0x6127).unwrap();
+        chip.execute(0x6212).unwrap();
+        chip.execute(0x63AE).unwrap();
+        chip.execute(0x64FF).unwrap();
+        chip.execute(0x65B4).unwrap();
+        chip.execute(0x6642).unwrap();
+        chip.execute(0x6F25).unwrap();
         chip
     }
 
@@ -29,10 +29,10 @@ mod input_tests {
     fn test_chip8_waits_for_keyboard_input() {
         let mut chip8 = set_up();
         let program_counter = chip8.get_pc();
-        chip8.cycle();
-        chip8.cycle();
-        chip8.cycle();
-        chip8.cycle();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
         assert_eq!(program_counter, chip8.get_pc());
     }
 
@@ -40,13 +40,17 @@ mod input_tests {
     fn test_chip8_continues_after_keyboard_input() {
         let mut chip8 = set_up();
         let program_counter = chip8.get_pc();
-        chip8.cycle();
-        chip8.cycle();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
         assert_eq!(program_counter, chip8.get_pc());
 
-        chip8.input.press(Key::Z, true);
-        chip8.cycle();
-        chip8.cycle();
+        chip8.input.key_down(0xA);
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        assert_eq!(program_counter, chip8.get_pc());
+
+        chip8.input.key_up(0xA);
+        chip8.cycle().unwrap();
         assert_eq!(0xA, chip8.get_v6());
     }
 
@@ -61,14 +65,14 @@ mod input_tests {
     #[test]
     fn skip_if_pressed() {
         let mut chip8 = set_up();
-        chip8.input.press(Key::Num1, true);
-        chip8.execute(0x6002);//Store 0x02 into V0
-        chip8.execute(0xE09E);//Skip if 0x02 is pressed (it isn't)
+        chip8.input.key_down(0x1);
+        chip8.execute(0x6002).unwrap();//Store 0x02 into V0
+        chip8.execute(0xE09E).unwrap();//Skip if 0x02 is pressed (it isn't)
         assert_eq!(0x200, chip8.get_pc());
         
-        chip8.input.press(Key::Num2, true);
-        chip8.execute(0x6002);//Store 0x02 into V0
-        chip8.execute(0xE09E);//Skip if 0x02 is pressed (it is)
+        chip8.input.key_down(0x2);
+        chip8.execute(0x6002).unwrap();//Store 0x02 into V0
+        chip8.execute(0xE09E).unwrap();//Skip if 0x02 is pressed (it is)
         assert_eq!(0x202, chip8.get_pc());
     }
 
@@ -83,14 +87,40 @@ mod input_tests {
     #[test]
     fn skip_if_not_pressed() {
         let mut chip8 = set_up();
-        chip8.input.press(Key::Num1, true);
-        chip8.execute(0x6002);//Store 0x02 into V0
-        chip8.execute(0xE0A1);//Skip if 0x02 is not pressed (it isn't)
+        chip8.input.key_down(0x1);
+        chip8.execute(0x6002).unwrap();//Store 0x02 into V0
+        chip8.execute(0xE0A1).unwrap();//Skip if 0x02 is not pressed (it isn't)
         assert_eq!(0x202, chip8.get_pc());
     
-        chip8.input.press(Key::Num2, true);
-        chip8.execute(0x6002);//Store 0x02 into V0
-        chip8.execute(0xE0A1);//Skip if 0x02 is pressed (it is)
+        chip8.input.key_down(0x2);
+        chip8.execute(0x6002).unwrap();//Store 0x02 into V0
+        chip8.execute(0xE0A1).unwrap();//Skip if 0x02 is pressed (it is)
+        assert_eq!(0x202, chip8.get_pc());
+    }
+
+    #[test]
+    fn test_key_up_and_mask() {
+        let mut chip8 = set_up();
+        chip8.input.key_down(0x1);
+        chip8.input.key_down(0xF);
+        chip8.input.key_down(0x10);
+        assert_eq!(0x8002, chip8.input.get_mask());
+
+        chip8.input.key_up(0x1);
+        assert!(!chip8.input.pressed(0x1));
+        chip8.input.set_mask(0x0400);
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        assert_eq!(0x200, chip8.get_pc());
+
+        // Releasing another key does not count; releasing 0xA does.
+        chip8.input.key_down(0x3);
+        chip8.input.key_up(0x3);
+        chip8.cycle().unwrap();
+        assert_eq!(0x200, chip8.get_pc());
+        chip8.input.key_up(0xA);
+        chip8.cycle().unwrap();
+        assert_eq!(0xA, chip8.get_v6());
         assert_eq!(0x202, chip8.get_pc());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod quirks_tests {
+    use crate::chip8::*;
+    use crate::quirks::{MemoryIncrement, Quirks};
+
+    fn set_up(quirks: Quirks) -> Chip8 {
+        let mut chip = init_chip();
+        chip.set_quirks(quirks);
+        chip.execute(0x6064).unwrap();
+        chip.execute(0x6127).unwrap();
+        chip.execute(0x6212).unwrap();
+        chip.execute(0x6F25).unwrap();
+        chip
+    }
+
+    #[test]
+    fn test_default_is_modern() {
+        assert_eq!(Quirks::modern(), init_chip().get_quirks());
+        assert_eq!(Some(Quirks::vip()), Quirks::from_name("VIP"));
+        assert_eq!(Some(Quirks::xo_chip()), Quirks::from_name("xo-chip"));
+        assert_eq!(None, Quirks::from_name("gameboy"));
+    }
+
+    /**
+     * On the COSMAC VIP 8XY6 and 8XYE store VY shifted into VX; later
+     * interpreters shift VX in place and ignore VY.
+    */
+    #[test]
+    fn test_shift_quirk() {
+        let mut chip8 = set_up(Quirks::vip());
+        chip8.execute(0x8016).unwrap(); // V0 = 0x27 >> 1
+        assert_eq!(0x13, chip8.get_v0());
+        assert_eq!(0x1, chip8.get_vf());
+
+        let mut chip8 = set_up(Quirks::schip());
+        chip8.execute(0x8016).unwrap(); // V0 = 0x64 >> 1
+        assert_eq!(0x32, chip8.get_v0());
+        assert_eq!(0x0, chip8.get_vf());
+    }
+
+    #[test]
+    fn test_memory_increment_quirk() {
+        let expected = [(MemoryIncrement::None, 0x300), (MemoryIncrement::X, 0x302), (MemoryIncrement::XPlusOne, 0x303)];
+        for (increment, i) in expected.iter() {
+            let mut quirks = Quirks::modern();
+            quirks.memory_increment = *increment;
+
+            let mut chip8 = set_up(quirks);
+            chip8.execute(0xA300).unwrap();
+            chip8.execute(0xF255).unwrap();
+            assert_eq!(*i, *chip8.get_i_register());
+
+            chip8.execute(0xA300).unwrap();
+            chip8.execute(0xF265).unwrap();
+            assert_eq!(*i, *chip8.get_i_register());
+            assert_eq!(0x27, chip8.get_v1());
+        }
+    }
+
+    #[test]
+    fn test_jump_quirk() {
+        let mut chip8 = set_up(Quirks::modern());
+        chip8.execute(0xB120).unwrap(); // 0x120 + V0
+        assert_eq!(0x184, chip8.get_pc());
+
+        let mut chip8 = set_up(Quirks::chip48());
+        chip8.execute(0xB120).unwrap(); // 0x120 + V1
+        assert_eq!(0x147, chip8.get_pc());
+    }
+
+    #[test]
+    fn test_vf_reset_quirk() {
+        let mut chip8 = set_up(Quirks::vip());
+        chip8.execute(0x8011).unwrap();
+        assert_eq!(0, chip8.get_vf());
+
+        let mut chip8 = set_up(Quirks::modern());
+        chip8.execute(0x8011).unwrap();
+        assert_eq!(0x25, chip8.get_vf());
+    }
+
+    /**
+     * An 8 pixel wide row drawn at X 60 either wraps its last 4 pixels to
+     * the left edge or loses them.
+    */
+    #[test]
+    fn test_clipping_quirk() {
+        let mut chip8 = set_up(Quirks::modern());
+        chip8.execute(0x60FF).unwrap();
+        chip8.execute(0xA300).unwrap();
+        chip8.execute(0xF055).unwrap(); // A full 8 pixel row at 0x300
+        chip8.execute(0x603C).unwrap();
+        chip8.execute(0x6100).unwrap();
+        chip8.execute(0xD011).unwrap(); // Draw it at 60, 0
+        assert_eq!(1, chip8.get_screen()[63]);
+        assert_eq!(1, chip8.get_screen()[0]);
+
+        let mut quirks = Quirks::modern();
+        quirks.clip_sprites = true;
+        chip8.set_quirks(quirks);
+        chip8.execute(0x00E0).unwrap();
+        chip8.execute(0xD011).unwrap();
+        assert_eq!(1, chip8.get_screen()[63]);
+        assert_eq!(0, chip8.get_screen()[0]);
+    }
+
+    /**
+     * With display wait only one sprite is drawn per 60 Hz tick; a second
+     * DXYN in the same tick is retried by stepping the PC back onto it.
+    */
+    #[test]
+    fn test_display_wait_quirk() {
+        let mut chip8 = set_up(Quirks::vip());
+        chip8.execute(0xD001).unwrap();
+        assert_eq!(0x200, chip8.get_pc());
+        chip8.execute(0xD001).unwrap();
+        assert_eq!(0x1FE, chip8.get_pc());
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod rom_tests {
+    use crate::chip8::*;
+    use crate::platform::Platform;
+    use crate::rom::{RomError, ROM_START};
+
+    #[test]
+    fn test_load_rom_reports_size() {
+        let mut chip8 = init_chip();
+        assert_eq!(34, chip8.load_rom("E03TestRom.ch8").unwrap());
+        assert_eq!(0x60, chip8.get_memory()[0x200]);
+        assert_eq!(0x15, chip8.get_memory()[0x201]);
+    }
+
+    #[test]
+    fn test_load_rom_from_bytes() {
+        let mut chip8 = init_chip();
+        assert_eq!(4, chip8.load_rom_bytes(&[0x60, 0x15, 0x61, 0x20]).unwrap());
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        assert_eq!(0x15, chip8.get_v0());
+        assert_eq!(0x20, chip8.get_v1());
+    }
+
+    #[test]
+    fn test_missing_rom() {
+        let mut chip8 = init_chip();
+        match chip8.load_rom("does-not-exist.ch8") {
+            Err(RomError::NotFound(_)) => {},
+            other => panic!("Expected NotFound, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_empty_rom() {
+        let mut chip8 = init_chip();
+        match chip8.load_rom_bytes(&[]) {
+            Err(RomError::Empty) => {},
+            other => panic!("Expected Empty, got {:?}", other)
+        }
+    }
+
+    /**
+     * Programs start at 0x200, so only 4096 - 0x200 bytes fit. A ROM that is
+     * too large must be rejected before anything is written to memory.
+    */
+    #[test]
+    fn test_rom_too_large() {
+        let mut chip8 = init_chip();
+        let max_size = Platform::Chip8.memory_size() - ROM_START;
+        let rom = vec![0xAA; max_size + 1];
+        match chip8.load_rom_bytes(&rom) {
+            Err(RomError::TooLarge { size, max }) => {
+                assert_eq!(max_size + 1, size);
+                assert_eq!(max_size, max);
+            },
+            other => panic!("Expected TooLarge, got {:?}", other)
+        }
+        assert_eq!(0, chip8.get_memory()[0x200]);
+
+        assert_eq!(max_size, chip8.load_rom_bytes(&rom[1..]).unwrap());
+        assert_eq!(0xAA, chip8.get_memory()[0xFFF]);
+    }
+
+    #[test]
+    fn test_reload_rom_resets_machine() {
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&[0x60, 0x15, 0x12, 0x00, 0xFF, 0xFF]).unwrap();
+        chip8.cycle().unwrap();
+        chip8.execute(0xA300).unwrap();
+        chip8.execute(0x2400).unwrap();
+
+        chip8.reload_rom_bytes(&[0x61, 0x20]).unwrap();
+        assert_eq!(0x200, chip8.get_pc());
+        assert_eq!(0, chip8.get_v0());
+        assert_eq!(0, *chip8.get_i_register());
+        assert_eq!(0, *chip8.get_sp());
+        assert_eq!(0x61, chip8.get_memory()[0x200]);
+        assert_eq!(0, chip8.get_memory()[0x204]);
+        assert_eq!(0xF0, chip8.get_memory()[0]);
+    }
+}
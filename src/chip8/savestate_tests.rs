@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod savestate_tests {
+    use crate::chip8::*;
+    use crate::platform::Platform;
+    use crate::savestate::{self, SaveStateError, VERSION};
+
+    // Counts V0 up, draws the matching digit and calls a subroutine that
+    // stores the registers, forever.
+    const COUNTER: [u8; 18] = [
+        0x70, 0x01, // 200: V0 += 1
+        0xF0, 0x29, // 202: I = font(V0)
+        0xD1, 0x15, // 204: draw at V1, V1
+        0x22, 0x0C, // 206: call 20C
+        0x12, 0x00, // 208: jump 200
+        0x00, 0x00,
+        0xA3, 0x00, // 20C: I = 300
+        0xF1, 0x55, // 20E: store V0, V1
+        0x00, 0xEE, // 210: return
+    ];
+
+    fn run(chip: &mut Chip8, cycles: usize) {
+        for _ in 0..cycles {
+            chip.cycle().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_state() {
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&COUNTER).unwrap();
+        chip8.input.set_key(0x5, true);
+        run(&mut chip8, 21);
+        assert_eq!(1, *chip8.get_sp());
+
+        let saved = chip8.save_state();
+        let snapshot = chip8.snapshot();
+        run(&mut chip8, 25);
+        chip8.input.set_key(0x5, false);
+        assert!(snapshot != chip8.snapshot());
+
+        // The state was saved with key 5 down, but loading it leaves the keypad
+        // as it is held now.
+        chip8.input.set_key(0xA, true);
+        chip8.load_state(&saved).unwrap();
+        assert_eq!(1 << 0xA, chip8.input.get_mask());
+        chip8.input.set_mask(1 << 5);
+        assert!(snapshot == chip8.snapshot());
+        assert_eq!(1, *chip8.get_sp());
+
+        // The restored machine carries on exactly like the original did.
+        let mut other = init_chip();
+        other.load_rom_bytes(&COUNTER).unwrap();
+        other.load_state(&saved).unwrap();
+        other.input.set_mask(1 << 5);
+        run(&mut chip8, 40);
+        run(&mut other, 40);
+        assert!(chip8.snapshot() == other.snapshot());
+    }
+
+    #[test]
+    fn test_state_is_tied_to_rom() {
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&COUNTER).unwrap();
+        let saved = chip8.save_state();
+
+        let mut other = init_chip();
+        other.load_rom_bytes(&[0x12, 0x00]).unwrap();
+        match other.load_state(&saved) {
+            Err(SaveStateError::RomMismatch { expected, found }) => {
+                assert_eq!(other.get_rom_hash(), expected);
+                assert_eq!(chip8.get_rom_hash(), found);
+            },
+            other => panic!("Expected RomMismatch, got {:?}", other.err())
+        }
+        assert_eq!(0x12, other.get_memory()[0x200]);
+    }
+
+    /**
+     * Damaged files are rejected with a reason and leave the machine as it was.
+    */
+    #[test]
+    fn test_bad_state_is_rejected() {
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&COUNTER).unwrap();
+        let saved = chip8.save_state();
+        run(&mut chip8, 3);
+
+        match chip8.load_state(&saved[..saved.len() - 1]) {
+            Err(SaveStateError::Truncated) => {},
+            other => panic!("Expected Truncated, got {:?}", other.err())
+        }
+        match chip8.load_state(b"PNG\0...") {
+            Err(SaveStateError::BadMagic) => {},
+            other => panic!("Expected BadMagic, got {:?}", other.err())
+        }
+        let mut newer = saved.clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        match chip8.load_state(&newer) {
+            Err(SaveStateError::UnsupportedVersion(version)) => assert_eq!(VERSION + 1, version),
+            other => panic!("Expected UnsupportedVersion, got {:?}", other.err())
+        }
+        assert_eq!(1, chip8.get_v0());
+        assert_eq!(0x206, chip8.get_pc());
+
+        // A frame that has supposedly run for ages ends with the next
+        // instruction instead of ticking the timers for ever.
+        let mut damaged = chip8.snapshot();
+        damaged.delay_timer = 10;
+        damaged.frame_cycles = u64::MAX;
+        chip8.load_state(&damaged.encode(chip8.get_rom_hash())).unwrap();
+        run(&mut chip8, 1);
+        assert_eq!(9, chip8.get_delay_timer());
+    }
+
+    #[test]
+    fn test_state_carries_random_sequence() {
+        let program = [0xC0, 0xFF, 0x12, 0x00]; // 200: V0 = random, jump 200
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&program).unwrap();
+        chip8.set_seed(0xC8);
+        run(&mut chip8, 10);
+        let saved = chip8.save_state();
+
+        let mut other = init_chip();
+        other.load_rom_bytes(&program).unwrap();
+        other.load_state(&saved).unwrap();
+        assert_eq!(0xC8, other.get_seed());
+        for _ in 0..5 {
+            run(&mut chip8, 2);
+            run(&mut other, 2);
+            assert_eq!(chip8.get_v0(), other.get_v0());
+        }
+    }
+
+    #[test]
+    fn test_state_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("chip8-savestate-test-{}.state", std::process::id()));
+        let mut chip8 = init_chip();
+        chip8.set_platform(Platform::XoChip);
+        chip8.load_rom_bytes(&COUNTER).unwrap();
+        chip8.execute(0x00FF).unwrap();
+        chip8.execute(0xAFFF).unwrap();
+        run(&mut chip8, 7);
+        savestate::save_to_file(&chip8, &path).unwrap();
+
+        let mut other = init_chip();
+        other.set_platform(Platform::XoChip);
+        other.load_rom_bytes(&COUNTER).unwrap();
+        savestate::load_from_file(&mut other, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(chip8.snapshot() == other.snapshot());
+        assert!(other.display.is_hires());
+        assert_eq!(0x10000, other.get_memory().len());
+    }
+}
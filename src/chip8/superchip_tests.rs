@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod superchip_tests {
+    use crate::chip8::*;
+    use crate::error::ErrorKind;
+    use crate::platform::Platform;
+
+    fn set_up() -> Chip8 {
+        let mut chip = init_chip();
+        chip.set_platform(Platform::SuperChip);
+        chip.execute(0x6064).unwrap();
+        chip.execute(0x6127).unwrap();
+        chip.execute(0x6212).unwrap();
+        chip.execute(0x6F25).unwrap();
+        chip
+    }
+
+    // Draws the 0 glyph (rows F0 90 90 90 F0) at VX, VY.
+    fn draw_zero(chip: &mut Chip8, x: u32, y: u32) {
+        chip.execute(0x6300 | x).unwrap();
+        chip.execute(0x6400 | y).unwrap();
+        chip.execute(0xA000).unwrap();
+        chip.execute(0xD345).unwrap();
+    }
+
+    #[test]
+    fn test_superchip_opcodes_need_platform() {
+        let mut chip8 = init_chip();
+        assert_eq!(ErrorKind::UnknownOpcode, chip8.execute(0x00FF).unwrap_err().kind);
+    }
+
+    /**
+     * 00FF switches to 128x64 and 00FE back to 64x32. Both clear the screen.
+    */
+    #[test]
+    fn test_resolution_switching() {
+        let mut chip8 = set_up();
+        draw_zero(&mut chip8, 0, 0);
+        chip8.execute(0x00FF).unwrap();
+        assert!(chip8.display.is_hires());
+        assert_eq!(128, chip8.display.get_width());
+        assert_eq!(0, chip8.display.get_pixel(0, 0));
+
+        draw_zero(&mut chip8, 100, 60);
+        assert_eq!(1, chip8.display.get_pixel(100, 60));
+        assert_eq!(1, chip8.display.get_pixel(100, 0)); // The last row wraps to the top
+
+        chip8.execute(0x00FE).unwrap();
+        assert_eq!(64, chip8.display.get_width());
+        assert_eq!(0, chip8.display.get_gfx()[60][100]);
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let mut chip8 = set_up();
+        chip8.execute(0x00FF).unwrap();
+        draw_zero(&mut chip8, 8, 0);
+
+        chip8.execute(0x00C3).unwrap(); // Down 3 lines
+        assert_eq!(0, chip8.display.get_pixel(8, 0));
+        assert_eq!(1, chip8.display.get_pixel(8, 3));
+
+        chip8.execute(0x00FB).unwrap(); // Right 4 pixels
+        assert_eq!(0, chip8.display.get_pixel(8, 3));
+        assert_eq!(1, chip8.display.get_pixel(12, 3));
+
+        chip8.execute(0x00FC).unwrap(); // Left 4 pixels
+        chip8.execute(0x00FC).unwrap();
+        assert_eq!(1, chip8.display.get_pixel(4, 3));
+        assert_eq!(0, chip8.display.get_pixel(12, 3));
+    }
+
+    /**
+     * DXY0 draws a 16x16 sprite from 32 bytes at I, two bytes per row.
+    */
+    #[test]
+    fn test_draw_16x16_sprite() {
+        let mut chip8 = init_chip();
+        chip8.set_platform(Platform::SuperChip);
+        let mut rom = vec![0x00, 0xFF, 0xA2, 0x08, 0x60, 0x00, 0xD0, 0x00];
+        for row in 0..16 {
+            rom.push(0x80);
+            rom.push(if row == 15 { 0x01 } else { 0x00 });
+        }
+        chip8.load_rom_bytes(&rom).unwrap();
+        for _ in 0..4 {
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(1, chip8.display.get_pixel(0, 0));
+        assert_eq!(1, chip8.display.get_pixel(0, 15));
+        assert_eq!(1, chip8.display.get_pixel(15, 15));
+        assert_eq!(0, chip8.display.get_pixel(15, 14));
+        assert_eq!(0, chip8.get_vf());
+    }
+
+    #[test]
+    fn test_big_font() {
+        let mut chip8 = set_up();
+        chip8.execute(0x6008).unwrap();
+        chip8.execute(0xF030).unwrap();
+        let i = *chip8.get_i_register() as usize;
+        assert_eq!([0xFF, 0xFF, 0xC3, 0xC3, 0xFF], chip8.get_memory()[i..i + 5]);
+    }
+
+    /**
+     * FX75 and FX85 save V0..VX to the RPL flags and back. The flags are
+     * persistent storage, so a reset keeps them.
+    */
+    #[test]
+    fn test_rpl_flags() {
+        let mut chip8 = set_up();
+        chip8.execute(0xF275).unwrap();
+        chip8.reset();
+        assert_eq!(0, chip8.get_v1());
+
+        chip8.execute(0xF185).unwrap();
+        assert_eq!(0x64, chip8.get_v0());
+        assert_eq!(0x27, chip8.get_v1());
+        assert_eq!(0, chip8.get_v2());
+    }
+
+    #[test]
+    fn test_exit() {
+        let mut chip8 = init_chip();
+        chip8.set_platform(Platform::SuperChip);
+        chip8.load_rom_bytes(&[0x00, 0xFD, 0x60, 0x01]).unwrap();
+        chip8.cycle().unwrap();
+        assert!(chip8.has_exited());
+        chip8.cycle().unwrap();
+        assert_eq!(0, chip8.get_v0());
+        assert_eq!(0x202, chip8.get_pc());
+    }
+}
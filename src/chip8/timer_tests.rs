@@ -1,53 +1,140 @@
 #[cfg(test)]
 mod timer_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
     use crate::chip8::*;
+    use crate::audio::{AudioSink, Sound};
 
     fn set_up() -> Chip8 {
         let mut chip = init_chip();
-        chip.load_rom(std::string::String::from("E05TimerLoop.ch8"));
-        chip.execute(0x6064);
-        chip.execute(0x6127);
-        chip.execute(0x6212);
-        chip.execute(0x63AE);
-        chip.execute(0x64FF);
-        chip.execute(0x65B4);
-        chip.execute(0x6642);
-        chip.execute(0x6F25);
+        chip.load_rom("E05TimerLoop.ch8").unwrap();
+        chip.execute(0x6064).unwrap();
+        chip.execute(0x6127).unwrap();
+        chip.execute(0x6212).unwrap();
+        chip.execute(0x63AE).unwrap();
+        chip.execute(0x64FF).unwrap();
+        chip.execute(0x65B4).unwrap();
+        chip.execute(0x6642).unwrap();
+        chip.execute(0x6F25).unwrap();
         chip
     }
 
     #[test]
     fn test_delay_timer_opcodes() {
         let mut chip8 = set_up();
-        chip8.execute(0xF015);
-        chip8.execute(0xF107);
+        chip8.execute(0xF015).unwrap();
+        chip8.execute(0xF107).unwrap();
         assert_eq!(0x64, chip8.get_v1());
     }
 
-// NEEDS FIXING
-    // #[test]
-    // fn test_delay_timer_counter() {
-    //     let mut chip8 = set_up();
-    //     while chip8.get_v5() != 255 {
-    //         chip8.cycle();
-    //     }
-    // }
+    /**
+     * The ROM sets the delay timer to 0x15 and waits for it, which takes 21
+     * frames of 700 / 60 instructions each.
+    */
+    #[test]
+    fn test_delay_timer_counter() {
+        let mut chip8 = set_up();
+        while chip8.get_v5() != 255 {
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(0, chip8.get_delay_timer());
+        assert_eq!(21, chip8.get_cycles() * 60 / DEFAULT_IPS as u64);
+    }
+
+    #[test]
+    fn test_timers_follow_ips() {
+        let mut chip8 = set_up();
+        chip8.set_ips(60);
+        while chip8.get_v5() != 255 {
+            chip8.cycle().unwrap();
+        }
+        assert!(chip8.get_cycles() < 30, "took {} instructions", chip8.get_cycles());
+    }
+
+    /**
+     * Slowing down after ten seconds neither catches up on the frames the
+     * lower speed would have had by now nor waits for them: the timer ticks
+     * once per new frame. A restored state carries where in its frame the
+     * machine was.
+    */
+    #[test]
+    fn test_ips_change_keeps_frame_phase() {
+        let mut chip8 = init_chip();
+        chip8.load_rom_bytes(&[0x12, 0x00]).unwrap(); // 200: jump 200
+        for _ in 0..DEFAULT_IPS * 10 + 5 {
+            chip8.cycle().unwrap();
+        }
+        chip8.execute(0x60C8).unwrap();
+        chip8.execute(0xF015).unwrap();
+        chip8.set_ips(60);
+        chip8.cycle().unwrap();
+        assert_eq!(199, chip8.get_delay_timer());
+        for _ in 0..10 {
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(189, chip8.get_delay_timer());
+
+        chip8.set_ips(DEFAULT_IPS);
+        for _ in 0..3 {
+            chip8.cycle().unwrap();
+        }
+        let saved = chip8.snapshot();
+        assert_eq!(9, chip8.cycles_left_in_frame());
+        chip8.reset();
+        chip8.restore(&saved);
+        assert_eq!(9, chip8.cycles_left_in_frame());
+        for _ in 0..9 {
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(188, chip8.get_delay_timer());
+    }
 
     #[test]
     fn test_sound_timer() {
         let mut chip8 = set_up();
-        chip8.execute(0xF018); //Set timer to 0x64
-        chip8.cycle();
-    }
-
-// NEEDS FIXING
-    // #[test]
-    // fn test_emit_sound_timer() {
-    //     let mut chip = init_chip();
-    //     chip.load_rom(std::string::String::from("E05SoundLoop.ch8"));
-    //     while chip.get_v5() != 255 {
-    //         chip.cycle();
-    //     }
-    // }
+        chip8.execute(0xF018).unwrap(); //Set timer to 0x64
+        chip8.cycle().unwrap();
+    }
+
+    #[test]
+    fn test_emit_sound_timer() {
+        let mut chip = init_chip();
+        chip.load_rom("E05SoundLoop.ch8").unwrap();
+        while chip.get_v5() != 255 {
+            chip.cycle().unwrap();
+        }
+        assert_eq!(0, chip.get_sound_timer());
+        assert_eq!(60, chip.get_cycles() * 60 / DEFAULT_IPS as u64);
+    }
+
+    struct Frames(Rc<RefCell<Vec<bool>>>);
+
+    impl AudioSink for Frames {
+        fn frame(&mut self, sound: Sound) {
+            self.0.borrow_mut().push(sound.sounding);
+        }
+    }
+
+    /**
+     * E05SoundLoop sets the sound timer to 0x3C, so the buzzer sounds for
+     * exactly one second of frames and then stays quiet.
+    */
+    #[test]
+    fn test_sound_loop_reaches_audio_sink() {
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        let mut chip = init_chip();
+        chip.load_rom("E05SoundLoop.ch8").unwrap();
+        chip.set_audio_sink(Box::new(Frames(frames.clone())));
+        while chip.get_v5() != 255 {
+            chip.cycle().unwrap();
+        }
+        for _ in 0..DEFAULT_IPS {
+            chip.cycle().unwrap();
+        }
+        let frames = frames.borrow();
+        assert_eq!(120, frames.len());
+        assert_eq!(60, frames.iter().filter(|&&sounding| sounding).count());
+        assert!(frames[..60].iter().all(|&sounding| sounding));
+    }
 
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod xochip_tests {
+    use crate::chip8::*;
+    use crate::error::ErrorKind;
+    use crate::platform::Platform;
+    use crate::rom::RomError;
+
+    fn xo_chip(rom: &[u8]) -> Chip8 {
+        let mut chip = init_chip();
+        chip.set_platform(Platform::XoChip);
+        chip.load_rom_bytes(rom).unwrap();
+        chip
+    }
+
+    #[test]
+    fn test_xochip_opcodes_need_platform() {
+        let mut chip8 = init_chip();
+        chip8.set_platform(Platform::SuperChip);
+        assert_eq!(ErrorKind::UnknownOpcode, chip8.execute(0xF201).unwrap_err().kind);
+    }
+
+    /**
+     * XO-CHIP has 64 KiB of memory, so ROMs may be far larger than 3.5 KiB
+     * and I can address all of it.
+    */
+    #[test]
+    fn test_64k_memory() {
+        let mut rom = vec![0; 0x8000];
+        rom[..4].copy_from_slice(&[0xF0, 0x00, 0xFF, 0xF0]);
+        rom[0x7FFF] = 0xAB;
+        let mut chip8 = xo_chip(&rom);
+        assert_eq!(0x10000, chip8.get_memory().len());
+
+        chip8.cycle().unwrap();
+        assert_eq!(0xFFF0, *chip8.get_i_register());
+        assert_eq!(0x204, chip8.get_pc());
+        assert_eq!(0xAB, chip8.get_memory()[0x81FF]);
+
+        match init_chip().load_rom_bytes(&rom) {
+            Err(RomError::TooLarge { .. }) => {},
+            other => panic!("Expected TooLarge, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_skip_over_long_load() {
+        let mut chip8 = xo_chip(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x61, 0x01]);
+        chip8.cycle().unwrap();
+        assert_eq!(0x206, chip8.get_pc());
+        chip8.cycle().unwrap();
+        assert_eq!(1, chip8.get_v1());
+    }
+
+    /**
+     * 5XY2 and 5XY3 save and load VX..VY without touching I. With X greater
+     * than Y the registers are stored in reverse order.
+    */
+    #[test]
+    fn test_register_ranges() {
+        let mut chip8 = xo_chip(&[0x00, 0xE0]);
+        chip8.execute(0x6211).unwrap();
+        chip8.execute(0x6322).unwrap();
+        chip8.execute(0x6433).unwrap();
+        chip8.execute(0xA300).unwrap();
+        chip8.execute(0x5242).unwrap();
+        assert_eq!([0x11, 0x22, 0x33], chip8.get_memory()[0x300..0x303]);
+        assert_eq!(0x300, *chip8.get_i_register());
+
+        chip8.execute(0xA400).unwrap();
+        chip8.execute(0x5422).unwrap();
+        assert_eq!([0x33, 0x22, 0x11], chip8.get_memory()[0x400..0x403]);
+
+        chip8.execute(0xA300).unwrap();
+        chip8.execute(0x5A83).unwrap();
+        assert_eq!(0x11, chip8.get_register(0xA));
+        assert_eq!(0x33, chip8.get_register(0x8));
+    }
+
+    /**
+     * With both planes selected a sprite takes N bytes for each plane, and
+     * clearing or scrolling only affects the selected planes.
+    */
+    #[test]
+    fn test_bitplanes() {
+        let mut chip8 = xo_chip(&[0x00, 0xE0]);
+        chip8.execute(0xF301).unwrap();
+        chip8.execute(0xA000).unwrap(); // The 0 and 1 glyphs
+        chip8.execute(0xD005).unwrap();
+        assert_eq!(3, chip8.display.get_pixel(2, 0));
+        assert_eq!(1, chip8.display.get_pixel(0, 0));
+        assert_eq!(2, chip8.display.get_pixel(1, 1));
+        assert_eq!(0, chip8.get_vf());
+
+        chip8.execute(0xF201).unwrap();
+        chip8.execute(0x00D1).unwrap(); // Scroll plane 2 up a line
+        assert_eq!(3, chip8.display.get_pixel(1, 0));
+        assert_eq!(2, chip8.display.get_pixel(2, 1));
+        assert_eq!(1, chip8.display.get_pixel(0, 1));
+
+        chip8.execute(0xA00A).unwrap();
+        chip8.execute(0xD001).unwrap();
+        assert_eq!(1, chip8.get_vf());
+
+        chip8.execute(0x00E0).unwrap();
+        assert_eq!(1, chip8.display.get_pixel(0, 0));
+        assert_eq!(1, chip8.display.get_pixel(2, 0));
+    }
+
+    #[test]
+    fn test_audio_pattern_and_pitch() {
+        let mut chip8 = xo_chip(&[0x00, 0xE0]);
+        assert_eq!(4000.0, chip8.get_playback_rate());
+
+        chip8.execute(0xA050).unwrap();
+        chip8.execute(0xF002).unwrap();
+        assert_eq!(chip8.get_memory()[0x50..0x60], chip8.get_audio_pattern()[..]);
+
+        chip8.execute(0x6070).unwrap();
+        chip8.execute(0xF03A).unwrap();
+        assert_eq!(0x70, chip8.get_pitch());
+        assert_eq!(8000.0, chip8.get_playback_rate());
+    }
+}
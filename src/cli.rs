@@ -0,0 +1,240 @@
+use std::convert::TryFrom;
+use std::fmt;
+mod cli_tests;
+
+use crate::audio::{ToneSettings, Waveform};
+use crate::error::OpcodePolicy;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::trace::TraceFormat;
+
+// The emulator binary's command line. It lives in the library, away from SDL,
+// so the checks can be tested.
+
+pub const USAGE: &str = "\
+Usage: emulator [OPTIONS] <ROM>
+
+Options:
+  --ips <N>          Instructions executed per second (default 700)
+  --scale <N>        Window pixels per low-resolution pixel, even (default 20)
+  --platform <NAME>  Instruction set: chip8 (default), schip or xochip
+  --quirks <NAME>    Quirk profile: vip, chip48, schip, xochip or modern
+                     (default: the usual profile for the platform)
+  --seed <N>         Seed for the CXNN random number generator, to make a
+                     run repeatable
+  --tone <HZ>        Pitch of the beep (default 440)
+  --wave <NAME>      Shape of the beep: square (default), sine or triangle
+  --volume <PERCENT> Loudness of the beep, 0 to 100 (default 25)
+  --mute             Start with the sound off (press M to toggle)
+  --record-audio <FILE>
+                     Also write the sound to FILE as WAV, in either mode
+  --keymap <FILE>    Keyboard and gamepad layout for the keypad; see
+                     src/keymap.rs
+  --bad-opcode <P>   On an unknown opcode: halt (default), skip or trap
+  --rewind <SECONDS> How much gameplay to keep for rewinding (default 300,
+                     0 turns rewinding off)
+  --rewind-budget <MIB>
+                     Memory the rewind buffer may use (default 32)
+  --paused           Start with emulation paused (press P to resume)
+  --debug            Start in the debugger prompt on stdin
+  --trace <FILE>     Write every executed instruction to FILE
+  --trace-format <F> text (default) or binary
+  --trace-range <START-END>
+                     Only trace instructions at these addresses
+  --trace-last <N>   Only write the last N instructions, when a fault
+                     stops the program
+  --headless         Run without opening a window, then print the screen
+  --frames <N>       Stop after N frames (required with --headless)
+  -h, --help         Print this help
+
+Keys:
+  P                  Pause or resume
+  M                  Mute or unmute
+  F1-F4              Select save state slot 1-4
+  F5, F9             Save or load the state in the selected slot
+  F7                 Look for gamepads plugged in or out
+  Backspace          Hold to rewind
+  F12                Break into the debugger prompt";
+
+pub struct Options {
+    pub rom: String,
+    pub ips: u32,
+    pub scale: u32,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub seed: Option<u64>,
+    pub tone: ToneSettings,
+    pub muted: bool,
+    pub record_audio: Option<String>,
+    pub keymap: Option<String>,
+    pub policy: OpcodePolicy,
+    pub paused: bool,
+    pub debug: bool,
+    pub headless: bool,
+    pub frames: Option<u32>,
+    pub rewind_seconds: u32,
+    pub rewind_budget: u32,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_range: Option<(u16, u16)>,
+    pub trace_last: Option<u32>,
+    pub help: bool
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, CliError> {
+    let mut options = Options {
+        rom: String::new(),
+        ips: 700,
+        scale: 20,
+        platform: Platform::default(),
+        quirks: Quirks::default(),
+        seed: None,
+        tone: ToneSettings::default(),
+        muted: false,
+        record_audio: None,
+        keymap: None,
+        policy: OpcodePolicy::Halt,
+        paused: false,
+        debug: false,
+        headless: false,
+        frames: None,
+        rewind_seconds: 300,
+        rewind_budget: 32,
+        trace: None,
+        trace_format: TraceFormat::Text,
+        trace_range: None,
+        trace_last: None,
+        help: false
+    };
+    let mut rom = None;
+    let mut quirks = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => options.help = true,
+            "--ips"         => options.ips = parse_u32(&arg, args.next())?,
+            "--scale"       => options.scale = parse_u32(&arg, args.next())?,
+            "--seed"        => options.seed = Some(parse_number(&arg, args.next())?),
+            "--frames"      => options.frames = Some(parse_u32(&arg, args.next())?),
+            "--rewind"      => options.rewind_seconds = parse_u32(&arg, args.next())?,
+            "--rewind-budget" => options.rewind_budget = parse_u32(&arg, args.next())?,
+            "--tone"        => options.tone.frequency = parse_u32(&arg, args.next())? as f32,
+            "--volume"      => options.tone.volume = parse_u32(&arg, args.next())? as f32 / 100.0,
+            "--mute"        => options.muted = true,
+            "--keymap"      => options.keymap = Some(value(&arg, args.next())?),
+            "--record-audio" => options.record_audio = Some(value(&arg, args.next())?),
+            "--wave"        => {
+                let name = value(&arg, args.next())?;
+                options.tone.waveform = Waveform::from_name(&name).ok_or_else(|| {
+                    CliError(format!("unknown waveform '{}', expected square, sine or triangle", name))
+                })?;
+            },
+            "--bad-opcode"  => {
+                options.policy = match value(&arg, args.next())?.to_lowercase().as_str() {
+                    "halt" => OpcodePolicy::Halt,
+                    "skip" => OpcodePolicy::Skip,
+                    "trap" => OpcodePolicy::Trap,
+                    other  => return Err(CliError(format!("unknown opcode policy '{}', expected halt, skip or trap", other)))
+                };
+            },
+            "--trace"       => options.trace = Some(value(&arg, args.next())?),
+            "--trace-last"  => options.trace_last = Some(parse_u32(&arg, args.next())?),
+            "--trace-format" => {
+                let name = value(&arg, args.next())?;
+                options.trace_format = TraceFormat::from_name(&name).ok_or_else(|| {
+                    CliError(format!("unknown trace format '{}', expected text or binary", name))
+                })?;
+            },
+            "--trace-range" => {
+                let raw = value(&arg, args.next())?;
+                let (start, end) = raw.split_at(raw.find('-').ok_or_else(|| {
+                    CliError(format!("--trace-range expects START-END, got '{}'", raw))
+                })?);
+                let start = parse_u32(&arg, Some(start.to_string()))?;
+                let end = parse_u32(&arg, Some(end[1..].to_string()))?;
+                if start > end || end > 0xFFFF {
+                    return Err(CliError(format!("--trace-range must be an address range like 0x200-0x2FF, got '{}'", raw)));
+                }
+                options.trace_range = Some((start as u16, end as u16));
+            },
+            "--paused"      => options.paused = true,
+            "--debug"       => options.debug = true,
+            "--headless"    => options.headless = true,
+            "--quirks"      => {
+                let name = value(&arg, args.next())?;
+                quirks = Some(Quirks::from_name(&name).ok_or_else(|| {
+                    CliError(format!("unknown quirk profile '{}', expected vip, chip48, schip, xochip or modern", name))
+                })?);
+            },
+            "--platform"    => {
+                let name = value(&arg, args.next())?;
+                options.platform = Platform::from_name(&name).ok_or_else(|| {
+                    CliError(format!("unknown platform '{}', expected chip8, schip or xochip", name))
+                })?;
+            },
+            _ if arg.starts_with('-') => return Err(CliError(format!("unknown option '{}'", arg))),
+            _ => {
+                if rom.is_some() {
+                    return Err(CliError(format!("unexpected argument '{}', only one ROM can be loaded", arg)));
+                }
+                rom = Some(arg);
+            }
+        }
+    }
+
+    if options.help {
+        return Ok(options);
+    }
+    options.quirks = quirks.unwrap_or_else(|| options.platform.default_quirks());
+    options.rom = rom.ok_or_else(|| CliError(String::from("no ROM given")))?;
+
+    if options.ips < 60 {
+        return Err(CliError(format!("--ips must be at least 60, got {}", options.ips)));
+    }
+    // High resolution halves the pixels, and they have to stay whole.
+    if options.scale < 2 || options.scale > 64 || !options.scale.is_multiple_of(2) {
+        return Err(CliError(format!("--scale must be an even number from 2 to 64, got {}", options.scale)));
+    }
+    if options.tone.frequency < 20.0 || options.tone.frequency > 20000.0 {
+        return Err(CliError(format!("--tone must be between 20 and 20000 Hz, got {}", options.tone.frequency)));
+    }
+    if options.tone.volume > 1.0 {
+        return Err(CliError(format!("--volume must be between 0 and 100, got {}", options.tone.volume * 100.0)));
+    }
+    if options.trace.is_none() && (options.trace_range.is_some() || options.trace_last.is_some()) {
+        return Err(CliError(String::from("--trace-range and --trace-last need --trace")));
+    }
+    if options.headless && options.frames.is_none() {
+        return Err(CliError(String::from("--headless needs --frames to know when to stop")));
+    }
+    Ok(options)
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, CliError> {
+    value.ok_or_else(|| CliError(format!("{} needs a value", option)))
+}
+
+// Accepts decimal or 0x-prefixed hexadecimal.
+fn parse_number(option: &str, raw: Option<String>) -> Result<u64, CliError> {
+    let raw = value(option, raw)?;
+    let parsed = if raw.starts_with("0x") || raw.starts_with("0X") {
+        u64::from_str_radix(&raw[2..], 16)
+    } else {
+        raw.parse::<u64>()
+    };
+    parsed.map_err(|_| CliError(format!("{} expects a number, got '{}'", option, raw)))
+}
+
+fn parse_u32(option: &str, raw: Option<String>) -> Result<u32, CliError> {
+    let number = parse_number(option, raw)?;
+    u32::try_from(number).map_err(|_| CliError(format!("{} is out of range: {}", option, number)))
+}
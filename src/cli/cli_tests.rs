@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod cli_tests {
+    use crate::cli::*;
+    use crate::audio::Waveform;
+    use crate::error::OpcodePolicy;
+    use crate::platform::Platform;
+    use crate::quirks::Quirks;
+    use crate::trace::TraceFormat;
+
+    fn parse_args(args: &str) -> Result<Options, CliError> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    fn error(args: &str) -> String {
+        parse_args(args).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = parse_args("pong.ch8").unwrap();
+        assert_eq!("pong.ch8", options.rom);
+        assert_eq!(700, options.ips);
+        assert_eq!(20, options.scale);
+        assert!(options.platform == Platform::Chip8);
+        assert!(options.quirks == Platform::Chip8.default_quirks());
+        assert_eq!(None, options.seed);
+        assert!(options.policy == OpcodePolicy::Halt);
+        assert!(!options.headless && !options.paused && !options.debug && !options.help);
+        assert!(parse_args("--help").unwrap().help);
+    }
+
+    /**
+     * Numbers can be given in decimal or with a 0x prefix in either case, and
+     * the platform picks the quirks unless --quirks says otherwise.
+    */
+    #[test]
+    fn test_options_and_numbers() {
+        let options = parse_args("--ips 0x3E8 --scale 10 --seed 0XFF --platform schip --wave sine \
+            --volume 50 --tone 880 --mute --bad-opcode skip --trace run.trace --trace-format binary \
+            --trace-range 0x200-0x2FF --trace-last 64 --headless --frames 120 game.ch8").unwrap();
+        assert_eq!(1000, options.ips);
+        assert_eq!(10, options.scale);
+        assert_eq!(Some(255), options.seed);
+        assert!(options.platform == Platform::SuperChip);
+        assert!(options.quirks == Platform::SuperChip.default_quirks());
+        assert!(options.tone.waveform == Waveform::Sine);
+        assert_eq!(0.5, options.tone.volume);
+        assert_eq!(880.0, options.tone.frequency);
+        assert!(options.muted);
+        assert!(options.policy == OpcodePolicy::Skip);
+        assert!(options.trace_format == TraceFormat::Binary);
+        assert_eq!(Some((0x200, 0x2FF)), options.trace_range);
+        assert_eq!(Some(64), options.trace_last);
+        assert_eq!(Some(120), options.frames);
+
+        let options = parse_args("--platform xochip --quirks vip game.ch8").unwrap();
+        assert!(options.quirks == Quirks::vip());
+    }
+
+    #[test]
+    fn test_range_checks() {
+        assert_eq!("--ips must be at least 60, got 59", error("--ips 59 pong"));
+        assert_eq!("--scale must be an even number from 2 to 64, got 0", error("--scale 0 pong"));
+        assert_eq!("--scale must be an even number from 2 to 64, got 1", error("--scale 1 pong"));
+        assert_eq!("--scale must be an even number from 2 to 64, got 21", error("--scale 21 pong"));
+        assert_eq!("--scale must be an even number from 2 to 64, got 66", error("--scale 66 pong"));
+        assert_eq!("--tone must be between 20 and 20000 Hz, got 19", error("--tone 19 pong"));
+        assert_eq!("--tone must be between 20 and 20000 Hz, got 20001", error("--tone 20001 pong"));
+        assert_eq!("--volume must be between 0 and 100, got 101", error("--volume 101 pong"));
+        assert_eq!("--ips is out of range: 4294967296", error("--ips 0x100000000 pong"));
+        assert!(parse_args("--ips 60 --scale 64 --tone 20 --volume 100 pong").is_ok());
+        assert!(parse_args("--scale 2 pong").is_ok());
+    }
+
+    #[test]
+    fn test_trace_range() {
+        assert_eq!("--trace-range expects START-END, got '0x200'", error("--trace t --trace-range 0x200 pong"));
+        assert_eq!("--trace-range must be an address range like 0x200-0x2FF, got '0x300-0x200'",
+            error("--trace t --trace-range 0x300-0x200 pong"));
+        assert_eq!("--trace-range must be an address range like 0x200-0x2FF, got '0-0x10000'",
+            error("--trace t --trace-range 0-0x10000 pong"));
+        assert_eq!("--trace-range expects a number, got 'x'", error("--trace t --trace-range 0x200-x pong"));
+    }
+
+    #[test]
+    fn test_bad_arguments() {
+        assert_eq!("no ROM given", error("--ips 700"));
+        assert_eq!("unexpected argument 'b', only one ROM can be loaded", error("a b"));
+        assert_eq!("unknown option '--fast'", error("--fast pong"));
+        assert_eq!("--scale needs a value", error("pong --scale"));
+        assert_eq!("--ips expects a number, got 'fast'", error("--ips fast pong"));
+        assert_eq!("--seed expects a number, got '0xZZ'", error("--seed 0xZZ pong"));
+        assert_eq!("unknown platform 'nes', expected chip8, schip or xochip", error("--platform nes pong"));
+        assert_eq!("unknown quirk profile 'x', expected vip, chip48, schip, xochip or modern", error("--quirks x pong"));
+        assert_eq!("unknown waveform 'saw', expected square, sine or triangle", error("--wave saw pong"));
+        assert_eq!("unknown opcode policy 'ignore', expected halt, skip or trap", error("--bad-opcode ignore pong"));
+        assert_eq!("unknown trace format 'json', expected text or binary", error("--trace-format json pong"));
+    }
+
+    #[test]
+    fn test_dependent_options() {
+        assert_eq!("--trace-range and --trace-last need --trace", error("--trace-last 10 pong"));
+        assert_eq!("--trace-range and --trace-last need --trace", error("--trace-range 0x200-0x300 pong"));
+        assert_eq!("--headless needs --frames to know when to stop", error("--headless pong"));
+        assert!(parse_args("--headless --frames 1 pong").is_ok());
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+mod condition_tests;
+
+use crate::chip8::Chip8;
+
+// A small expression language for debugger conditions, e.g.
+// `V3 == 5 && I > 0x300` or `[0x2F0] != 0`. Everything evaluates to an
+// unsigned number; comparisons and logic give 1 or 0 and any non-zero value
+// counts as true.
+//
+// Operands: V0-VF, I, PC, SP, DT, ST, decimal or 0x numbers, and [EXPR] for
+// the memory byte at an address. Operators from loosest to tightest:
+// ||, &&, == != < <= > >=, |, &, + -, and the unary !.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(u32),
+    Register(usize),
+    I,
+    Pc,
+    Sp,
+    Delay,
+    Sound,
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Or, And, Eq, Ne, Lt, Le, Gt, Ge, BitOr, BitAnd, Add, Sub
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    source: String,
+    expr: Expr
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Condition, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        let expr = parser.expression(0)?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected '{}'", token));
+        }
+        Ok(Condition { source: source.trim().to_string(), expr })
+    }
+
+    pub fn eval(&self, chip: &Chip8) -> u32 {
+        eval(&self.expr, chip)
+    }
+
+    pub fn is_true(&self, chip: &Chip8) -> bool {
+        self.eval(chip) != 0
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn eval(expr: &Expr, chip: &Chip8) -> u32 {
+    match expr {
+        Expr::Number(n) => *n,
+        Expr::Register(x) => chip.get_register(*x),
+        Expr::I => *chip.get_i_register(),
+        Expr::Pc => chip.get_pc(),
+        Expr::Sp => *chip.get_sp(),
+        Expr::Delay => chip.get_delay_timer(),
+        Expr::Sound => chip.get_sound_timer(),
+        Expr::Memory(address) => {
+            let address = eval(address, chip) as usize;
+            chip.get_memory().get(address).map_or(0, |&byte| byte as u32)
+        },
+        Expr::Not(inner) => (eval(inner, chip) == 0) as u32,
+        Expr::Binary(op, left, right) => {
+            let left = eval(left, chip);
+            // || and && short-circuit like they do everywhere else.
+            match op {
+                Op::Or if left != 0 => return 1,
+                Op::And if left == 0 => return 0,
+                _ => {}
+            }
+            let right = eval(right, chip);
+            match op {
+                Op::Or | Op::And => (right != 0) as u32,
+                Op::Eq => (left == right) as u32,
+                Op::Ne => (left != right) as u32,
+                Op::Lt => (left < right) as u32,
+                Op::Le => (left <= right) as u32,
+                Op::Gt => (left > right) as u32,
+                Op::Ge => (left >= right) as u32,
+                Op::BitOr => left | right,
+                Op::BitAnd => left & right,
+                Op::Add => left.wrapping_add(right),
+                Op::Sub => left.wrapping_sub(right)
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphanumeric() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() { i += 1 }
+            tokens.push(chars[start..i].iter().collect());
+        } else {
+            let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if ["==", "!=", "<=", ">=", "&&", "||"].contains(&pair.as_str()) {
+                tokens.push(pair);
+                i += 2;
+            } else if "<>!&|+-()[]".contains(c) {
+                tokens.push(c.to_string());
+                i += 1;
+            } else {
+                return Err(format!("unexpected character '{}'", c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize
+}
+
+// Binding strength of each binary operator; higher binds tighter.
+fn binary_op(token: &str) -> Option<(Op, u8)> {
+    let op = match token {
+        "||" => (Op::Or, 1),
+        "&&" => (Op::And, 2),
+        "==" => (Op::Eq, 3),
+        "!=" => (Op::Ne, 3),
+        "<" => (Op::Lt, 3),
+        "<=" => (Op::Le, 3),
+        ">" => (Op::Gt, 3),
+        ">=" => (Op::Ge, 3),
+        "|" => (Op::BitOr, 4),
+        "&" => (Op::BitAnd, 5),
+        "+" => (Op::Add, 6),
+        "-" => (Op::Sub, 6),
+        _ => return None
+    };
+    Some(op)
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected '{}', got '{}'", expected, token)),
+            None => Err(format!("expected '{}' at the end", expected))
+        }
+    }
+
+    // Precedence climbing: parses operators that bind tighter than `min`.
+    fn expression(&mut self, min: u8) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some((op, strength)) = self.peek().and_then(binary_op) {
+            if strength <= min {
+                break;
+            }
+            self.pos += 1;
+            let right = self.expression(strength)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some("!") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some("(") => {
+                let inner = self.expression(0)?;
+                self.expect(")")?;
+                Ok(inner)
+            },
+            Some("[") => {
+                let address = self.expression(0)?;
+                self.expect("]")?;
+                Ok(Expr::Memory(Box::new(address)))
+            },
+            Some(token) => operand(token),
+            None => Err(String::from("expression ends too early"))
+        }
+    }
+}
+
+fn operand(token: &str) -> Result<Expr, String> {
+    let upper = token.to_uppercase();
+    let expr = match upper.as_str() {
+        "I" => Expr::I,
+        "PC" => Expr::Pc,
+        "SP" => Expr::Sp,
+        "DT" => Expr::Delay,
+        "ST" => Expr::Sound,
+        _ if upper.len() == 2 && upper.starts_with('V') => {
+            let x = usize::from_str_radix(&upper[1..], 16).map_err(|_| format!("unknown register '{}'", token))?;
+            Expr::Register(x)
+        },
+        _ if upper.starts_with("0X") => {
+            Expr::Number(u32::from_str_radix(&upper[2..], 16).map_err(|_| format!("bad number '{}'", token))?)
+        },
+        _ => Expr::Number(upper.parse().map_err(|_| format!("unknown operand '{}'", token))?)
+    };
+    Ok(expr)
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod condition_tests {
+    use crate::condition::*;
+    use crate::chip8::init_chip;
+
+    #[test]
+    fn test_operands_and_precedence() {
+        let mut chip8 = init_chip();
+        chip8.execute(0x6305).unwrap();
+        chip8.execute(0xA310).unwrap();
+        let eval = |source: &str| Condition::parse(source).unwrap().eval(&chip8);
+
+        assert_eq!(1, eval("V3 == 5 && I > 0x300"));
+        assert_eq!(0, eval("V3 == 5 && I > 0x310"));
+        assert_eq!(1, eval("v3 != 5 || i == 0x310"));
+        assert_eq!(9, eval("V3 + 2 + 2"));
+        assert_eq!(1, eval("V3 + 1 == 6"));
+        assert_eq!(4, eval("V3 & 6 | 0"));
+        assert_eq!(1, eval("!(V3 < 5)"));
+        assert_eq!(0x200, eval("PC"));
+        assert_eq!(0xF0, eval("[0]"));
+        assert_eq!(0x90, eval("[V3 - 4]"));
+        assert_eq!(0, eval("[0xFFFFF]"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Condition::parse("V3 ==").is_err());
+        assert!(Condition::parse("VG == 1").is_err());
+        assert!(Condition::parse("(V3 == 1").is_err());
+        assert!(Condition::parse("V3 = 1").is_err());
+        assert!(Condition::parse("V3 1").is_err());
+        assert_eq!("V3 == 5", Condition::parse("  V3 == 5 ").unwrap().to_string());
+    }
+}
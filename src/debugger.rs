@@ -0,0 +1,554 @@
+use std::collections::BTreeMap;
+use std::fmt;
+mod debugger_tests;
+mod watch_tests;
+
+use crate::chip8::Chip8;
+use crate::condition::Condition;
+use crate::error::ExecError;
+use crate::history::History;
+use crate::instruction::{decode, Instruction};
+use crate::watch::{MemoryAccess, Watchpoint};
+
+// Why a run command handed control back to the prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    // Single step finished.
+    Stepped,
+    // The target of step-over, step-out or run-to was reached.
+    Reached,
+    Breakpoint(u32),
+    Fault(ExecError),
+    Exited,
+    // Gave up after the cycle limit, e.g. a game spinning on a key press.
+    Limit,
+    // A watchpoint fired after the instruction at `pc`. `number` is the one
+    // shown by the watch list, counting from 1.
+    MemoryWatch { number: usize, pc: u32, access: MemoryAccess },
+    ValueChanged { number: usize, pc: u32, old: u32, new: u32 },
+    ConditionMet { number: usize, pc: u32 },
+    // Going backwards ran out of recorded history.
+    HistoryStart,
+    // Step-out was asked for outside any subroutine; nothing ran.
+    NotInSubroutine
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Stepped | StopReason::Reached => Ok(()),
+            StopReason::Breakpoint(address) => write!(f, "breakpoint at {:03X}", address),
+            StopReason::Fault(e) => write!(f, "{}", e),
+            StopReason::Exited => write!(f, "program exited"),
+            StopReason::Limit => write!(f, "gave up at the cycle limit"),
+            StopReason::MemoryWatch { number, pc, access } => write!(f, "watchpoint {}: {} by {:03X}", number, access, pc),
+            StopReason::ValueChanged { number, pc, old, new } => write!(f, "watchpoint {}: {:#X} -> {:#X} by {:03X}", number, old, new, pc),
+            StopReason::ConditionMet { number, pc } => write!(f, "watchpoint {}: condition met after {:03X}", number, pc),
+            StopReason::HistoryStart => write!(f, "reached the start of the recorded history"),
+            StopReason::NotInSubroutine => write!(f, "not inside a subroutine")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Step(u32),
+    Next,
+    Finish,
+    Until(u32),
+    Continue,
+    StepBack(u32),
+    ReverseContinue(Option<Condition>),
+    Break(u32, Option<Condition>),
+    Delete(u32),
+    Breakpoints,
+    Watch(Watchpoint),
+    Unwatch(usize),
+    Watches,
+    Registers,
+    List(usize),
+    Help,
+    Quit
+}
+
+pub const HELP: &str = "\
+Commands:
+  s, step [N]        Execute N instructions (default 1)
+  n, next            Step over a CALL, stopping after it returns
+  f, finish          Run until the current subroutine returns
+  u, until <ADDR>    Run until PC reaches ADDR
+  c, continue        Resume emulation until a breakpoint
+  sb, stepback [N]   Go back N instructions (default 1)
+  rc, rcontinue [ADDR | if EXPR]
+                     Go back to the last breakpoint that was hit, or to
+                     the last time PC was ADDR or EXPR held
+  b, break <ADDR> [if EXPR]
+                     Set a breakpoint, optionally only taken when EXPR holds
+  b, break if EXPR   Stop wherever EXPR becomes true
+  d, delete <ADDR>   Remove a breakpoint
+  bl                 List breakpoints
+  w, watch <ADDR> [LEN] [r|w|rw]
+                     Stop after an instruction reads or writes the bytes
+  wv <EXPR>          Stop when the value of EXPR changes, e.g. wv V3
+  wl                 List watchpoints
+  uw, unwatch <N>    Remove watchpoint N
+  r, regs            Show registers, stack and timers
+  l, list [N]        Disassemble N instructions from PC (default 8)
+  h, help            Show this help
+  q, quit            Quit the emulator
+Addresses and counts are hexadecimal with 0x, decimal otherwise. EXPR uses
+V0-VF, I, PC, SP, DT, ST, [ADDR] for a memory byte, numbers and the
+operators || && == != < <= > >= | & + - !, e.g. V3 == 5 && I > 0x300.";
+
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, rest) = match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, "")
+    };
+    let args: Vec<&str> = rest.split_whitespace().collect();
+    let single = || -> Result<Option<&str>, String> {
+        match args.len() {
+            0 | 1 => Ok(args.first().cloned()),
+            _ => Err(format!("unexpected argument '{}'", args[1]))
+        }
+    };
+
+    let command = match name {
+        "s" | "step" => Command::Step(optional_number(single()?, 1)?),
+        "n" | "next" => Command::Next,
+        "f" | "finish" => Command::Finish,
+        "u" | "until" => Command::Until(required_number(name, single()?)?),
+        "c" | "continue" => Command::Continue,
+        "sb" | "stepback" => Command::StepBack(optional_number(single()?, 1)?),
+        "rc" | "rcontinue" => {
+            if let Some(condition) = rest.strip_prefix("if ") {
+                Command::ReverseContinue(Some(Condition::parse(condition)?))
+            } else {
+                match single()? {
+                    Some(address) => Command::ReverseContinue(Some(Condition::parse(&format!("PC == {:#X}", parse_number(address)?))?)),
+                    None => Command::ReverseContinue(None)
+                }
+            }
+        },
+        "b" | "break" => {
+            if let Some(condition) = rest.strip_prefix("if ") {
+                Command::Watch(Watchpoint::Condition(Condition::parse(condition)?))
+            } else {
+                let address = required_number(name, args.first().cloned())?;
+                let condition = match rest.find(" if ") {
+                    Some(i) => Some(Condition::parse(&rest[i + 4..])?),
+                    None if args.len() > 1 => return Err(format!("unexpected argument '{}', expected if", args[1])),
+                    None => None
+                };
+                Command::Break(address, condition)
+            }
+        },
+        "d" | "delete" => Command::Delete(required_number(name, single()?)?),
+        "bl" => Command::Breakpoints,
+        "w" | "watch" => {
+            let start = required_number(name, args.first().cloned())?;
+            let (len, kind) = match args.get(1) {
+                Some(arg) if arg.starts_with(|c: char| c.is_ascii_digit()) => (parse_number(arg)?, args.get(2)),
+                _ => (1, args.get(1))
+            };
+            let (reads, writes) = match kind.cloned() {
+                None | Some("rw") => (true, true),
+                Some("r") => (true, false),
+                Some("w") => (false, true),
+                Some(other) => return Err(format!("expected r, w or rw, got '{}'", other))
+            };
+            if len == 0 {
+                return Err(String::from("a watchpoint needs at least one byte"));
+            }
+            Command::Watch(Watchpoint::Memory { start, len, reads, writes })
+        },
+        "wv" => Command::Watch(Watchpoint::Value(Condition::parse(rest)?)),
+        "wl" => Command::Watches,
+        "uw" | "unwatch" => Command::Unwatch(required_number(name, single()?)? as usize),
+        "r" | "regs" => Command::Registers,
+        "l" | "list" => Command::List(optional_number(single()?, 8)? as usize),
+        "h" | "help" => Command::Help,
+        "q" | "quit" => Command::Quit,
+        "" => return Err(String::from("no command given")),
+        other => return Err(format!("unknown command '{}', try help", other))
+    };
+    let takes_arguments = matches!(command, Command::Step(_) | Command::Until(_) | Command::Break(..) |
+        Command::StepBack(_) | Command::ReverseContinue(_) |
+        Command::Delete(_) | Command::Watch(_) | Command::Unwatch(_) | Command::List(_));
+    if !takes_arguments && !args.is_empty() {
+        return Err(format!("unexpected argument '{}'", args[0]));
+    }
+    Ok(command)
+}
+
+fn parse_number(raw: &str) -> Result<u32, String> {
+    let parsed = if raw.starts_with("0x") || raw.starts_with("0X") {
+        u32::from_str_radix(&raw[2..], 16)
+    } else {
+        raw.parse::<u32>()
+    };
+    parsed.map_err(|_| format!("expected a number, got '{}'", raw))
+}
+
+fn optional_number(arg: Option<&str>, default: u32) -> Result<u32, String> {
+    arg.map_or(Ok(default), parse_number)
+}
+
+fn required_number(command: &str, arg: Option<&str>) -> Result<u32, String> {
+    parse_number(arg.ok_or_else(|| format!("{} needs an address", command))?)
+}
+
+// What the prompt should do after a command.
+pub struct Outcome {
+    pub output: String,
+    pub resume: bool,
+    pub quit: bool
+}
+
+impl Outcome {
+    fn show(output: String) -> Outcome {
+        Outcome { output, resume: false, quit: false }
+    }
+}
+
+// Run commands give up after this many cycles so a prompt never hangs on a
+// target that is not reached.
+pub const DEFAULT_LIMIT: u64 = 10_000_000;
+
+// How far back step-back and reverse-continue can go: a snapshot every
+// CHECKPOINT_INTERVAL cycles, CHECKPOINTS of them.
+pub const CHECKPOINT_INTERVAL: u64 = 1000;
+pub const CHECKPOINTS: usize = 200;
+
+pub struct Debugger {
+    breakpoints: BTreeMap<u32, Option<Condition>>,
+    watchpoints: Vec<Watchpoint>,
+    history: History,
+    limit: u64
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: BTreeMap::new(),
+            watchpoints: Vec::new(),
+            history: History::new(CHECKPOINT_INTERVAL, CHECKPOINTS),
+            limit: DEFAULT_LIMIT
+        }
+    }
+
+    // Returns false if the breakpoint was already set.
+    pub fn add_breakpoint(&mut self, address: u32) -> bool {
+        self.breakpoints.insert(address, None).is_none()
+    }
+
+    // A breakpoint that is only taken when `condition` holds. Replaces any
+    // breakpoint already at the address.
+    pub fn add_conditional_breakpoint(&mut self, address: u32, condition: Condition) {
+        self.breakpoints.insert(address, Some(condition));
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u32) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    pub fn has_breakpoint(&self, address: u32) -> bool {
+        self.breakpoints.contains_key(&address)
+    }
+
+    // Whether the instruction at PC is a breakpoint that should be taken now.
+    pub fn breaks_at(&self, chip: &Chip8) -> bool {
+        breaks_at(&self.breakpoints, chip)
+    }
+
+    // Returns the watchpoint's number.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        self.watchpoints.push(watchpoint);
+        self.watchpoints.len()
+    }
+
+    // Takes the number add_watchpoint returned. Later watchpoints move up.
+    pub fn remove_watchpoint(&mut self, number: usize) -> Option<Watchpoint> {
+        if number == 0 || number > self.watchpoints.len() {
+            return None;
+        }
+        Some(self.watchpoints.remove(number - 1))
+    }
+
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    // Forgets the recorded history, e.g. after loading a save state.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    // Runs one instruction and checks the watchpoints against what it did.
+    pub fn step(&mut self, chip: &mut Chip8) -> StopReason {
+        self.history.record(chip);
+        let pc = chip.get_pc();
+        chip.set_memory_tracing(self.watchpoints.iter().any(|w| matches!(w, Watchpoint::Memory { .. })));
+        let before: Vec<u32> = self.watchpoints.iter().map(|watchpoint| match watchpoint {
+            Watchpoint::Value(expr) | Watchpoint::Condition(expr) => expr.eval(chip),
+            Watchpoint::Memory { .. } => 0
+        }).collect();
+
+        match chip.cycle() {
+            Err(e) => return StopReason::Fault(e),
+            Ok(()) if chip.has_exited() => return StopReason::Exited,
+            Ok(()) => {}
+        }
+
+        let accesses = chip.take_memory_accesses();
+        for (i, watchpoint) in self.watchpoints.iter().enumerate() {
+            let number = i + 1;
+            match watchpoint {
+                Watchpoint::Memory { .. } => {
+                    if let Some(&access) = accesses.iter().find(|access| watchpoint.matches(access)) {
+                        return StopReason::MemoryWatch { number, pc, access };
+                    }
+                },
+                Watchpoint::Value(expr) => {
+                    let new = expr.eval(chip);
+                    if new != before[i] {
+                        return StopReason::ValueChanged { number, pc, old: before[i], new };
+                    }
+                },
+                Watchpoint::Condition(condition) => {
+                    if before[i] == 0 && condition.is_true(chip) {
+                        return StopReason::ConditionMet { number, pc };
+                    }
+                }
+            }
+        }
+        StopReason::Stepped
+    }
+
+    // One cycle for a frontend running at full speed: stops before a
+    // breakpoint and after a watchpoint fires, and returns None otherwise.
+    pub fn run_cycle(&mut self, chip: &mut Chip8) -> Result<Option<StopReason>, ExecError> {
+        if self.breaks_at(chip) {
+            return Ok(Some(StopReason::Breakpoint(chip.get_pc())));
+        }
+        match self.step(chip) {
+            StopReason::Fault(e) => Err(e),
+            StopReason::Stepped | StopReason::Exited => Ok(None),
+            stop => Ok(Some(stop))
+        }
+    }
+
+    // Runs a CALL and the whole subroutine as one step. Anything else is a
+    // plain step.
+    pub fn step_over(&mut self, chip: &mut Chip8) -> StopReason {
+        match next_instruction(chip) {
+            Some(Instruction::Call { .. }) => {
+                let return_address = chip.get_pc() + 2;
+                let sp = *chip.get_sp();
+                self.run_until(chip, |chip| chip.get_pc() == return_address && *chip.get_sp() == sp)
+            },
+            _ => self.step(chip)
+        }
+    }
+
+    // Runs until the RET that leaves the current subroutine.
+    pub fn step_out(&mut self, chip: &mut Chip8) -> StopReason {
+        let sp = *chip.get_sp();
+        if sp == 0 {
+            return StopReason::NotInSubroutine;
+        }
+        self.run_until(chip, |chip| *chip.get_sp() < sp)
+    }
+
+    pub fn run_to(&mut self, chip: &mut Chip8, address: u32) -> StopReason {
+        self.run_until(chip, |chip| chip.get_pc() == address)
+    }
+
+    pub fn resume(&mut self, chip: &mut Chip8) -> StopReason {
+        self.run_until(chip, |_| false)
+    }
+
+    // Always executes at least one instruction, so resuming from a breakpoint
+    // does not stop on it again straight away.
+    fn run_until<F: Fn(&Chip8) -> bool>(&mut self, chip: &mut Chip8, done: F) -> StopReason {
+        for _ in 0..self.limit {
+            match self.step(chip) {
+                StopReason::Stepped => {},
+                other => return other
+            }
+            if done(chip) {
+                return StopReason::Reached;
+            }
+            if self.breaks_at(chip) {
+                return StopReason::Breakpoint(chip.get_pc());
+            }
+        }
+        StopReason::Limit
+    }
+
+    // Undoes up to `count` instructions by replaying from the last snapshot
+    // before the target.
+    pub fn step_back(&mut self, chip: &mut Chip8, count: u32) -> StopReason {
+        let undone = self.history.step_back(chip, count as u64);
+        chip.take_memory_accesses();
+        if undone < count as u64 { StopReason::HistoryStart } else { StopReason::Stepped }
+    }
+
+    // Goes back to the most recent point where a breakpoint would have been
+    // taken, or where `condition` held if one is given.
+    pub fn reverse_continue(&mut self, chip: &mut Chip8, condition: Option<&Condition>) -> StopReason {
+        let breakpoints = &self.breakpoints;
+        let found = self.history.reverse_until(chip, |chip| match condition {
+            Some(condition) => condition.is_true(chip),
+            None => breaks_at(breakpoints, chip)
+        });
+        chip.take_memory_accesses();
+        match (found, condition) {
+            (None, _) => StopReason::HistoryStart,
+            (Some(_), Some(_)) => StopReason::Reached,
+            (Some(_), None) => StopReason::Breakpoint(chip.get_pc())
+        }
+    }
+
+    pub fn run_command(&mut self, chip: &mut Chip8, command: Command) -> Outcome {
+        let stop = match command {
+            Command::Step(count) => {
+                let mut stop = StopReason::Stepped;
+                for _ in 0..count {
+                    stop = self.step(chip);
+                    if stop != StopReason::Stepped { break }
+                }
+                stop
+            },
+            Command::Next => self.step_over(chip),
+            Command::Finish => self.step_out(chip),
+            Command::Until(address) => self.run_to(chip, address),
+            Command::StepBack(count) => self.step_back(chip, count),
+            Command::ReverseContinue(condition) => self.reverse_continue(chip, condition.as_ref()),
+            // Step off the current instruction here; the frontend checks the
+            // breakpoints from then on while it runs the game.
+            Command::Continue => {
+                return match self.step(chip) {
+                    StopReason::Stepped => Outcome { output: String::new(), resume: true, quit: false },
+                    stop => Outcome::show(format!("{}\n{}", stop, listing(chip, chip.get_pc(), 1)))
+                };
+            },
+            Command::Break(address, None) => {
+                let message = if self.add_breakpoint(address) { "set" } else { "already set" };
+                return Outcome::show(format!("breakpoint at {:03X} {}", address, message));
+            },
+            Command::Break(address, Some(condition)) => {
+                let message = format!("breakpoint at {:03X} if {} set", address, condition);
+                self.add_conditional_breakpoint(address, condition);
+                return Outcome::show(message);
+            },
+            Command::Delete(address) => {
+                let message = if self.remove_breakpoint(address) { "removed" } else { "was not set" };
+                return Outcome::show(format!("breakpoint at {:03X} {}", address, message));
+            },
+            Command::Breakpoints => {
+                let lines: Vec<String> = self.breakpoints.iter().map(|(address, condition)| match condition {
+                    Some(condition) => format!("{:03X} if {}", address, condition),
+                    None => format!("{:03X}", address)
+                }).collect();
+                return Outcome::show(if lines.is_empty() { String::from("no breakpoints") } else { lines.join("\n") });
+            },
+            Command::Watch(watchpoint) => {
+                let message = format!("{}", watchpoint);
+                let number = self.add_watchpoint(watchpoint);
+                return Outcome::show(format!("watchpoint {}: {}", number, message));
+            },
+            Command::Unwatch(number) => {
+                return Outcome::show(match self.remove_watchpoint(number) {
+                    Some(watchpoint) => format!("removed watchpoint {}: {}", number, watchpoint),
+                    None => format!("there is no watchpoint {}", number)
+                });
+            },
+            Command::Watches => {
+                let lines: Vec<String> = self.watchpoints.iter().enumerate().map(|(i, w)| format!("{}: {}", i + 1, w)).collect();
+                return Outcome::show(if lines.is_empty() { String::from("no watchpoints") } else { lines.join("\n") });
+            },
+            Command::Registers => return Outcome::show(registers_view(chip)),
+            Command::List(count) => return Outcome::show(listing(chip, chip.get_pc(), count)),
+            Command::Help => return Outcome::show(String::from(HELP)),
+            Command::Quit => return Outcome { output: String::new(), resume: false, quit: true }
+        };
+
+        let mut output = stop.to_string();
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&listing(chip, chip.get_pc(), 1));
+        Outcome::show(output)
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+fn breaks_at(breakpoints: &BTreeMap<u32, Option<Condition>>, chip: &Chip8) -> bool {
+    match breakpoints.get(&chip.get_pc()) {
+        Some(Some(condition)) => condition.is_true(chip),
+        Some(None) => true,
+        None => false
+    }
+}
+
+fn next_instruction(chip: &Chip8) -> Option<Instruction> {
+    let pc = chip.get_pc() as usize;
+    let memory = chip.get_memory();
+    if pc + 1 >= memory.len() {
+        return None;
+    }
+    decode(((memory[pc] as u16) << 8) | memory[pc + 1] as u16).ok()
+}
+
+// `count` instructions from `address`, one per line with the address and raw
+// word. Words that do not decode are shown as data.
+pub fn listing(chip: &Chip8, address: u32, count: usize) -> String {
+    let memory = chip.get_memory();
+    let mut lines = Vec::new();
+    let mut address = address as usize;
+    for _ in 0..count {
+        if address + 1 >= memory.len() {
+            break;
+        }
+        let word = ((memory[address] as u16) << 8) | memory[address + 1] as u16;
+        let marker = if address as u32 == chip.get_pc() { ">" } else { " " };
+        let text = match decode(word) {
+            Ok(Instruction::LoadILong) if address + 3 < memory.len() => {
+                let long = ((memory[address + 2] as u16) << 8) | memory[address + 3] as u16;
+                lines.push(format!("{} {:03X}  {:04X}  LD I, {:#06X}", marker, address, word, long));
+                address += 4;
+                continue;
+            },
+            Ok(instruction) => instruction.to_string(),
+            Err(_) => format!("DW {:#06X}", word)
+        };
+        lines.push(format!("{} {:03X}  {:04X}  {}", marker, address, word, text));
+        address += 2;
+    }
+    lines.join("\n")
+}
+
+// V0-VF, then PC, I, SP, the timers and the return addresses on the stack.
+pub fn registers_view(chip: &Chip8) -> String {
+    let names: Vec<String> = (0..16).map(|x| format!("V{:X}", x)).collect();
+    let values: Vec<String> = (0..16).map(|x| format!("{:02X}", chip.get_register(x))).collect();
+    let sp = *chip.get_sp() as usize;
+    let stack: Vec<String> = chip.get_stack()[..sp].iter().map(|a| format!("{:03X}", a)).collect();
+    format!(
+        "{}\n{}\nPC {:03X}  I {:03X}  SP {}  DT {:02X}  ST {:02X}\nStack: {}",
+        names.join(" "), values.join(" "),
+        chip.get_pc(), chip.get_i_register(), sp, chip.get_delay_timer(), chip.get_sound_timer(),
+        if stack.is_empty() { String::from("-") } else { stack.join(" ") }
+    )
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod debugger_tests {
+    use crate::debugger::*;
+    use crate::chip8::{init_chip, Chip8};
+
+    const PROGRAM: [u8; 16] = [
+        0x60, 0x05, // 200: V0 = 5
+        0x22, 0x0A, // 202: call 20A
+        0x61, 0x07, // 204: V1 = 7
+        0x12, 0x04, // 206: jump 204
+        0x00, 0x00,
+        0x70, 0x01, // 20A: V0 += 1
+        0x70, 0x01, // 20C: V0 += 1
+        0x00, 0xEE, // 20E: return
+    ];
+
+    fn set_up() -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(&PROGRAM).unwrap();
+        chip
+    }
+
+    #[test]
+    fn test_step_over_call() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        assert_eq!(StopReason::Stepped, debugger.step_over(&mut chip8));
+        assert_eq!(0x202, chip8.get_pc());
+
+        assert_eq!(StopReason::Reached, debugger.step_over(&mut chip8));
+        assert_eq!(0x204, chip8.get_pc());
+        assert_eq!(7, chip8.get_v0());
+        assert_eq!(0, *chip8.get_sp());
+    }
+
+    #[test]
+    fn test_step_out_and_run_to() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        assert_eq!(StopReason::Reached, debugger.run_to(&mut chip8, 0x20C));
+        assert_eq!(6, chip8.get_v0());
+        assert_eq!(1, *chip8.get_sp());
+
+        assert_eq!(StopReason::Reached, debugger.step_out(&mut chip8));
+        assert_eq!(0x204, chip8.get_pc());
+        assert_eq!(7, chip8.get_v0());
+
+        let cycles = chip8.get_cycles();
+        assert_eq!(StopReason::NotInSubroutine, debugger.step_out(&mut chip8));
+        assert_eq!(cycles, chip8.get_cycles());
+    }
+
+    /**
+     * Resuming from a breakpoint runs past it, and stops the next time round
+     * the loop. A target that is never reached stops at the cycle limit.
+    */
+    #[test]
+    fn test_breakpoints() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        assert!(debugger.add_breakpoint(0x204));
+        assert!(!debugger.add_breakpoint(0x204));
+
+        assert_eq!(StopReason::Breakpoint(0x204), debugger.resume(&mut chip8));
+        assert_eq!(0, chip8.get_v1());
+        assert_eq!(StopReason::Breakpoint(0x204), debugger.resume(&mut chip8));
+        assert_eq!(7, chip8.get_v1());
+
+        assert!(debugger.remove_breakpoint(0x204));
+        debugger.set_limit(100);
+        assert_eq!(StopReason::Limit, debugger.run_to(&mut chip8, 0x300));
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Ok(Command::Step(1)), parse_command("s"));
+        assert_eq!(Ok(Command::Step(16)), parse_command("step 0x10"));
+        assert_eq!(Ok(Command::Break(0x2A4, None)), parse_command("  b 0x2A4 "));
+        assert_eq!(Ok(Command::List(8)), parse_command("list"));
+        assert_eq!(Ok(Command::StepBack(4)), parse_command("sb 4"));
+        assert_eq!(Ok(Command::ReverseContinue(None)), parse_command("rc"));
+        assert_eq!(Ok(Command::ReverseContinue(Some(Condition::parse("PC == 0x2A4").unwrap()))), parse_command("rc 0x2A4"));
+        assert!(parse_command("until").is_err());
+        assert!(parse_command("b 0x200 0x300").is_err());
+        assert!(parse_command("jump").is_err());
+        assert!(parse_command("next 3").is_err());
+    }
+
+    #[test]
+    fn test_views() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.run_command(&mut chip8, Command::Step(2));
+        let listing = debugger.run_command(&mut chip8, Command::List(3)).output;
+        assert_eq!("> 20A  7001  ADD V0, 0x01\n  20C  7001  ADD V0, 0x01\n  20E  00EE  RET", listing);
+
+        let registers = debugger.run_command(&mut chip8, Command::Registers).output;
+        assert!(registers.starts_with("V0 V1 V2"));
+        assert!(registers.contains("\n05 00 00"), "{}", registers);
+        assert!(registers.contains("PC 20A  I 000  SP 1"), "{}", registers);
+        assert!(registers.ends_with("Stack: 204"), "{}", registers);
+
+        let outcome = debugger.run_command(&mut chip8, Command::Continue);
+        assert!(outcome.resume);
+        assert_eq!(0x20C, chip8.get_pc());
+        assert!(debugger.run_command(&mut chip8, Command::Quit).quit);
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod watch_tests {
+    use crate::debugger::*;
+    use crate::chip8::{init_chip, Chip8};
+    use crate::condition::Condition;
+    use crate::watch::{AccessKind, MemoryAccess, Watchpoint};
+
+    const PROGRAM: [u8; 18] = [
+        0xA3, 0x00, // 200: I = 300
+        0x60, 0x07, // 202: V0 = 7
+        0xF0, 0x33, // 204: BCD of V0 to 300..302
+        0xF2, 0x65, // 206: load V0..V2 from 300
+        0x73, 0x01, // 208: V3 += 1
+        0xD0, 0x11, // 20A: draw 1 byte from 300
+        0x12, 0x08, // 20C: jump 208
+        0x00, 0x00,
+        0x00, 0x00,
+    ];
+
+    fn set_up() -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(&PROGRAM).unwrap();
+        chip
+    }
+
+    /**
+     * Memory watchpoints report the instruction that touched the range and
+     * can be limited to reads or writes.
+    */
+    #[test]
+    fn test_memory_watchpoints() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.add_watchpoint(Watchpoint::Memory { start: 0x302, len: 1, reads: false, writes: true });
+        assert_eq!(StopReason::MemoryWatch { number: 1, pc: 0x204, access: MemoryAccess { kind: AccessKind::Write, address: 0x300, len: 3 } },
+                   debugger.resume(&mut chip8));
+
+        debugger.remove_watchpoint(1);
+        debugger.add_watchpoint(Watchpoint::Memory { start: 0x300, len: 1, reads: true, writes: false });
+        assert_eq!(StopReason::MemoryWatch { number: 1, pc: 0x206, access: MemoryAccess { kind: AccessKind::Read, address: 0x300, len: 3 } },
+                   debugger.resume(&mut chip8));
+        match debugger.resume(&mut chip8) {
+            StopReason::MemoryWatch { pc, .. } => assert_eq!(0x20A, pc),
+            other => panic!("Expected a sprite fetch, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_value_and_condition_watchpoints() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.add_watchpoint(Watchpoint::Value(Condition::parse("V2").unwrap()));
+        assert_eq!(StopReason::ValueChanged { number: 1, pc: 0x206, old: 0, new: 7 }, debugger.resume(&mut chip8));
+
+        debugger.remove_watchpoint(1);
+        debugger.add_watchpoint(Watchpoint::Condition(Condition::parse("V3 == 3").unwrap()));
+        assert_eq!(StopReason::ConditionMet { number: 1, pc: 0x208 }, debugger.resume(&mut chip8));
+        assert_eq!(3, chip8.get_register(3));
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.add_conditional_breakpoint(0x20A, Condition::parse("V3 >= 4 && I == 0x300").unwrap());
+        assert_eq!(StopReason::Breakpoint(0x20A), debugger.resume(&mut chip8));
+        assert_eq!(4, chip8.get_register(3));
+    }
+
+    #[test]
+    fn test_watch_commands() {
+        let watch = |line: &str| match parse_command(line) {
+            Ok(Command::Watch(watchpoint)) => watchpoint,
+            other => panic!("Expected a watchpoint from '{}', got {:?}", line, other)
+        };
+        assert_eq!(Watchpoint::Memory { start: 0x300, len: 1, reads: true, writes: true }, watch("w 0x300"));
+        assert_eq!(Watchpoint::Memory { start: 0x300, len: 4, reads: false, writes: true }, watch("watch 0x300 4 w"));
+        assert_eq!(Watchpoint::Memory { start: 0x300, len: 1, reads: true, writes: false }, watch("w 0x300 r"));
+        assert_eq!(Watchpoint::Condition(Condition::parse("V3 == 5").unwrap()), watch("b if V3 == 5"));
+        assert!(parse_command("w 0x300 0").is_err());
+        assert!(parse_command("w 0x300 x").is_err());
+
+        assert_eq!(Ok(Command::Break(0x2A4, Some(Condition::parse("V3 == 5 && I > 0x300").unwrap()))),
+                   parse_command("b 0x2A4 if V3 == 5 && I > 0x300"));
+
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.run_command(&mut chip8, parse_command("wv [0x302]").unwrap());
+        assert_eq!("1: value of [0x302]", debugger.run_command(&mut chip8, Command::Watches).output);
+        let output = debugger.run_command(&mut chip8, Command::Until(0x300)).output;
+        assert!(output.starts_with("watchpoint 1: 0x0 -> 0x7 by 204\n"), "{}", output);
+    }
+}
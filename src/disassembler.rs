@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::fmt;
+mod disassembler_tests;
+
+use crate::instruction::{decode, Instruction};
+use crate::platform::Platform;
+use crate::rom::ROM_START;
+
+// Turns a ROM back into a listing. Code is told apart from data by following
+// every path the program can take from 0x200: whatever is never reached that
+// way is shown as bytes. That is a guess in both directions. A computed jump
+// (BNNN) only has its base address followed, and bytes that are both sprite
+// data and code are shown as code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineKind {
+    Code(Instruction),
+    Data
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub kind: LineKind,
+    // The instruction or data directive, with jump targets named by label.
+    pub text: String
+}
+
+pub struct Disassembly {
+    pub lines: Vec<Line>,
+    // Label names by address, for jump and call targets and the data that
+    // LD I points at.
+    pub labels: BTreeMap<u16, String>
+}
+
+// How many data bytes go on one line.
+const DATA_PER_LINE: usize = 4;
+
+pub fn disassemble(rom: &[u8], platform: Platform) -> Disassembly {
+    let code = trace_code(rom, platform);
+    let labels = find_labels(rom, &code);
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let address = (ROM_START + offset) as u16;
+        if let Some(instruction) = code[offset] {
+            let len = instruction_len(instruction);
+            let text = instruction_text(instruction, &rom[offset..offset + len], &labels);
+            lines.push(Line { address, bytes: rom[offset..offset + len].to_vec(), kind: LineKind::Code(instruction), text });
+            offset += len;
+        } else {
+            // A data line ends early at code or at a label, so every label
+            // starts a line of its own.
+            let mut end = offset + 1;
+            while end < rom.len() && end - offset < DATA_PER_LINE && code[end].is_none()
+                && !labels.contains_key(&((ROM_START + end) as u16)) {
+                end += 1;
+            }
+            let bytes = rom[offset..end].to_vec();
+            let values: Vec<String> = bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+            lines.push(Line { address, bytes, kind: LineKind::Data, text: format!("DB {}", values.join(", ")) });
+            offset = end;
+        }
+    }
+    Disassembly { lines, labels }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.address) {
+                writeln!(f, "{}:", label)?;
+            }
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let raw: String = self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        write!(f, "  {:03X}  {:<8}  {}", self.address, raw, self.text)
+    }
+}
+
+// Marks the start of every instruction that can be reached from 0x200, with
+// the instruction itself.
+fn trace_code(rom: &[u8], platform: Platform) -> Vec<Option<Instruction>> {
+    let mut code = vec![None; rom.len()];
+    let mut pending = vec![ROM_START];
+    while let Some(address) = pending.pop() {
+        let offset = match address.checked_sub(ROM_START) {
+            Some(offset) if offset < rom.len() && code[offset].is_none() => offset,
+            _ => continue
+        };
+        let instruction = match instruction_at(rom, offset, platform) {
+            Some(instruction) => instruction,
+            None => continue
+        };
+        code[offset] = Some(instruction);
+        let next = address + instruction_len(instruction);
+        match instruction {
+            Instruction::Jump { nnn } | Instruction::JumpOffset { nnn } => pending.push(nnn as usize),
+            Instruction::Call { nnn } => {
+                pending.push(nnn as usize);
+                pending.push(next);
+            },
+            Instruction::Return | Instruction::Exit => {},
+            Instruction::SkipEqImm { .. } | Instruction::SkipNeImm { .. } | Instruction::SkipEqReg { .. } |
+            Instruction::SkipNeReg { .. } | Instruction::SkipKey { .. } | Instruction::SkipNotKey { .. } => {
+                pending.push(next);
+                // XO-CHIP skips the whole of a following F000 NNNN.
+                let skipped = instruction_at(rom, next - ROM_START, platform).map_or(2, instruction_len);
+                pending.push(next + skipped);
+            },
+            _ => pending.push(next)
+        }
+    }
+    code
+}
+
+fn find_labels(rom: &[u8], code: &[Option<Instruction>]) -> BTreeMap<u16, String> {
+    let mut labels = BTreeMap::new();
+    for (offset, instruction) in code.iter().enumerate() {
+        let target = match instruction {
+            Some(Instruction::Jump { nnn }) | Some(Instruction::JumpOffset { nnn }) |
+            Some(Instruction::Call { nnn }) | Some(Instruction::LoadI { nnn }) => *nnn,
+            Some(Instruction::LoadILong) => {
+                ((rom[offset + 2] as u16) << 8) | rom[offset + 3] as u16
+            },
+            _ => continue
+        };
+        // Only addresses inside the ROM get a name; the font and anything past
+        // the end are left as numbers.
+        if (target as usize) >= ROM_START && (target as usize) < ROM_START + rom.len() {
+            let prefix = match code[target as usize - ROM_START] {
+                Some(_) => "L",
+                None => "D"
+            };
+            labels.insert(target, format!("{}{:03X}", prefix, target));
+        }
+    }
+    labels
+}
+
+fn instruction_at(rom: &[u8], offset: usize, platform: Platform) -> Option<Instruction> {
+    if offset + 1 >= rom.len() {
+        return None;
+    }
+    let instruction = decode(((rom[offset] as u16) << 8) | rom[offset + 1] as u16).ok()?;
+    if instruction.platform() > platform {
+        return None;
+    }
+    // F000 is followed by its address; without it the word is not code.
+    if instruction == Instruction::LoadILong && offset + 3 >= rom.len() {
+        return None;
+    }
+    Some(instruction)
+}
+
+fn instruction_len(instruction: Instruction) -> usize {
+    match instruction {
+        Instruction::LoadILong => 4,
+        _ => 2
+    }
+}
+
+fn instruction_text(instruction: Instruction, bytes: &[u8], labels: &BTreeMap<u16, String>) -> String {
+    let target = |address: u16| match labels.get(&address) {
+        Some(label) => label.clone(),
+        None => format!("{:#05X}", address)
+    };
+    match instruction {
+        Instruction::Jump { nnn } => format!("JP {}", target(nnn)),
+        Instruction::JumpOffset { nnn } => format!("JP V0, {}", target(nnn)),
+        Instruction::Call { nnn } => format!("CALL {}", target(nnn)),
+        Instruction::LoadI { nnn } => format!("LD I, {}", target(nnn)),
+        Instruction::LoadILong => {
+            let address = ((bytes[2] as u16) << 8) | bytes[3] as u16;
+            match labels.get(&address) {
+                Some(label) => format!("LD I, long {}", label),
+                None => format!("LD I, long {:#06X}", address)
+            }
+        },
+        other => other.to_string()
+    }
+}
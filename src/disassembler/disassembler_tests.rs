@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod disassembler_tests {
+    use crate::disassembler::*;
+
+    const PROGRAM: [u8; 18] = [
+        0xA2, 0x0E, // 200: I = sprite
+        0x22, 0x0A, // 202: call 20A
+        0x3F, 0x01, // 204: skip if VF == 1
+        0x12, 0x02, // 206: jump 202
+        0x00, 0xFD, // 208: exit
+        0xD0, 0x15, // 20A: draw
+        0x00, 0xEE, // 20C: return
+        0xF0, 0x90, 0xF0, 0x90, // 20E: sprite
+    ];
+
+    #[test]
+    fn test_listing() {
+        let listing = disassemble(&PROGRAM, Platform::SuperChip).to_string();
+        assert_eq!("  200  A20E      LD I, D20E
+L202:
+  202  220A      CALL L20A
+  204  3F01      SE VF, 0x01
+  206  1202      JP L202
+  208  00FD      EXIT
+L20A:
+  20A  D015      DRW V0, V1, 5
+  20C  00EE      RET
+D20E:
+  20E  F090F090  DB 0xF0, 0x90, 0xF0, 0x90
+", listing);
+    }
+
+    /**
+     * 00FD is not a CHIP-8 instruction, so on that platform the path stops
+     * there and the bytes are shown as data.
+     */
+    #[test]
+    fn test_platform_decides_what_is_code() {
+        let disassembly = disassemble(&PROGRAM, Platform::Chip8);
+        let line = disassembly.lines.iter().find(|line| line.address == 0x208).unwrap();
+        assert_eq!(LineKind::Data, line.kind);
+        assert_eq!("DB 0x00, 0xFD", line.text);
+    }
+
+    #[test]
+    fn test_xochip_long_load_is_skipped_whole() {
+        let program = [
+            0x30, 0x00,             // 200: skip if V0 == 0
+            0xF0, 0x00, 0x02, 0x0A, // 202: I = 20A
+            0x12, 0x06,             // 206: loop
+            0x00, 0x00,
+            0x3C, 0x3C,             // 20A: data
+        ];
+        let disassembly = disassemble(&program, Platform::XoChip);
+        let texts: Vec<&str> = disassembly.lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(vec!["SE V0, 0x00", "LD I, long D20A", "JP L206", "DB 0x00, 0x00", "DB 0x3C, 0x3C"], texts);
+    }
+}
@@ -1,44 +1,136 @@
-use sdl::video;
-use sdl::Rect;
+// The framebuffer is always allocated at SUPER-CHIP high resolution; in low
+// resolution only the top-left 64x32 pixels are used.
+pub const MAX_WIDTH: usize = 128;
+pub const MAX_HEIGHT: usize = 64;
+
+// Each pixel holds one bit per XO-CHIP bitplane, so it is a colour index from
+// 0 to 3. Plain CHIP-8 and SUPER-CHIP only ever use the first plane.
+pub type Framebuffer = [[u8; MAX_WIDTH]; MAX_HEIGHT];
+
+pub const PLANE_COUNT: usize = 2;
+
+// A backend puts a finished frame in front of the user. The framebuffer itself
+// always lives in Display, so the core never needs a real video surface.
+// `width` and `height` give the part of `gfx` in use for the current mode.
+pub trait DisplayBackend {
+    fn present(&mut self, gfx: &Framebuffer, width: usize, height: usize);
+}
 
 pub struct Display {
-    gfx: [[u8; 64]; 32],
+    gfx: Framebuffer,
+    width: usize,
+    height: usize,
+    planes: u8,
     draw_flag: bool,
-    screen: video::Surface
+    backend: Box<dyn DisplayBackend>
 }
 
-static scale: isize = 20;
-
 impl Display {
-    pub fn new() -> Display {
+    pub fn new(backend: Box<dyn DisplayBackend>) -> Display {
         Display {
-            gfx: [[0; 64]; 32],
+            gfx: [[0; MAX_WIDTH]; MAX_HEIGHT],
+            width: 64,
+            height: 32,
+            planes: 1,
             draw_flag: true,
-            screen: video::set_video_mode(64*scale, 32*scale, 8,
-                                          &[video::SurfaceFlag::HWSurface],
-                                          &[video::VideoFlag::DoubleBuf]).unwrap()
+            backend
         }
     }
 
+    pub fn headless() -> Display {
+        Display::new(Box::new(HeadlessDisplay))
+    }
+
+    // Clears the selected planes only, as XO-CHIP does for 00E0.
     pub fn clear(&mut self) {
-        self.gfx = [[0; 64]; 32];
+        for line in self.gfx.iter_mut() {
+            for pixel in line.iter_mut() {
+                *pixel &= !self.planes;
+            }
+        }
+        self.draw_flag = true;
+    }
+
+    // Switches between 64x32 and 128x64. Like SUPER-CHIP, this clears the
+    // whole screen, whatever planes are selected.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.width = if hires { MAX_WIDTH } else { 64 };
+        self.height = if hires { MAX_HEIGHT } else { 32 };
+        self.gfx = [[0; MAX_WIDTH]; MAX_HEIGHT];
         self.draw_flag = true;
     }
 
-    pub fn draw(&mut self, x: usize, y: usize, sprite: &[u8]) -> u8 {
+    // Selects the bitplanes that drawing, clearing and scrolling act on, as a
+    // mask: 1 is the first plane, 2 the second and 3 both.
+    pub fn set_planes(&mut self, planes: u8) {
+        self.planes = planes & 0x3;
+    }
+
+    pub fn get_planes(&self) -> u8 {
+        self.planes
+    }
+
+    pub fn plane_count(&self) -> usize {
+        self.planes.count_ones() as usize
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.width == MAX_WIDTH
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    // XORs an 8 pixel wide sprite onto the screen and reports whether any pixel
+    // was turned off. The start position always wraps; with `clip` set the
+    // rows and columns that run past the edge are dropped instead of wrapping.
+    // With more than one plane selected, `sprite` holds the data for each
+    // plane in turn, lowest plane first.
+    pub fn draw(&mut self, x: usize, y: usize, sprite: &[u8], clip: bool) -> u8 {
+        let mut collision = 0;
+        let len = (sprite.len() / self.plane_count().max(1)).max(1);
+        for (plane, data) in self.selected_planes().zip(sprite.chunks(len)) {
+            let rows = data.iter().map(|&row| (row as u16) << 8);
+            collision |= self.blit(x, y, rows, clip, plane);
+        }
+        collision
+    }
+
+    // Draws a SUPER-CHIP 16x16 sprite, stored as two bytes per row.
+    pub fn draw_wide(&mut self, x: usize, y: usize, sprite: &[u8], clip: bool) -> u8 {
+        let mut collision = 0;
+        for (plane, data) in self.selected_planes().zip(sprite.chunks(32)) {
+            let rows = data.chunks(2).map(|pair| ((pair[0] as u16) << 8) | pair[1] as u16);
+            collision |= self.blit(x, y, rows, clip, plane);
+        }
+        collision
+    }
+
+    fn selected_planes(&self) -> impl Iterator<Item = u8> {
+        let planes = self.planes;
+        (0..PLANE_COUNT).map(|i| 1 << i).filter(move |bit| planes & bit != 0)
+    }
+
+    fn blit<I: Iterator<Item = u16>>(&mut self, x: usize, y: usize, rows: I, clip: bool, plane: u8) -> u8 {
         let mut collision = 0u8;
-        let n = sprite.len() as usize;
-        let mut yj: usize;
-        let mut xi: usize;
-
-        for j in 0..n {
-            for i in 0..8 {
-                yj = (y + j) % 32;
-                xi = (x + i) % 64;
-
-                if (sprite[j] & (0x80 >> i)) != 0 {
-                    if self.gfx[yj][xi] == 1 { collision = 1 }
-                    self.gfx[yj][xi] ^= 1;
+        let x = x % self.width;
+        let y = y % self.height;
+
+        for (j, row) in rows.enumerate() {
+            if clip && y + j >= self.height { break }
+            for i in 0..16 {
+                if clip && x + i >= self.width { break }
+                let yj = (y + j) % self.height;
+                let xi = (x + i) % self.width;
+
+                if (row & (0x8000 >> i)) != 0 {
+                    if self.gfx[yj][xi] & plane != 0 { collision = 1 }
+                    self.gfx[yj][xi] ^= plane;
                 }
             }
         }
@@ -47,21 +139,68 @@ impl Display {
         collision
     }
 
-    pub fn draw_screen(&mut self) {
-        if !self.draw_flag { return }
-        let mut pixel: u8;
-        let sc = scale as u16;
-        let pt = |p: usize| { (p as i16) * (scale as i16) };
-
-        for y in 0..32 {
-            for x in 0..64 {
-                pixel = if self.gfx[y][x] != 0 { 255 } else { 0 };
-                self.screen.fill_rect(Some(Rect { x: pt(x), y: pt(y), w: sc, h: sc}),
-                video::RGB(pixel, pixel, pixel));
+    // Scrolling moves the selected planes by (dx, dy) and leaves the others
+    // where they are. Pixels shifted in from outside the screen are blank.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let old = self.gfx;
+        let (width, height) = (self.width as isize, self.height as isize);
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let moved = if from_x >= 0 && from_x < width && from_y >= 0 && from_y < height {
+                    old[from_y as usize][from_x as usize]
+                } else {
+                    0
+                };
+                let pixel = &mut self.gfx[y as usize][x as usize];
+                *pixel = (*pixel & !self.planes) | (moved & self.planes);
             }
         }
+        self.draw_flag = true;
+    }
+
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll(0, n as isize);
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll(0, -(n as isize));
+    }
+
+    pub fn scroll_right(&mut self, n: usize) {
+        self.scroll(n as isize, 0);
+    }
+
+    pub fn scroll_left(&mut self, n: usize) {
+        self.scroll(-(n as isize), 0);
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> u8 {
+        self.gfx[y % self.height][x % self.width]
+    }
+
+    pub fn get_gfx(&self) -> &Framebuffer {
+        &self.gfx
+    }
 
-        self.screen.flip();
+    // Puts back a screen captured with get_gfx, e.g. from a save state.
+    pub fn restore(&mut self, gfx: &Framebuffer, hires: bool, planes: u8) {
+        self.set_hires(hires);
+        self.set_planes(planes);
+        self.gfx = *gfx;
+    }
+
+    pub fn draw_screen(&mut self) {
+        if !self.draw_flag { return }
+        self.backend.present(&self.gfx, self.width, self.height);
         self.draw_flag = false;
     }
-}
\ No newline at end of file
+}
+
+// Renders nothing: the framebuffer stays in memory inside Display, which is all
+// the tests and batch tooling need when running without a window.
+pub struct HeadlessDisplay;
+
+impl DisplayBackend for HeadlessDisplay {
+    fn present(&mut self, _gfx: &Framebuffer, _width: usize, _height: usize) {}
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt;
+
+// What the interpreter does when it meets an opcode it does not know.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpcodePolicy {
+    // Stop the machine; every later cycle returns the same error until reset.
+    Halt,
+    // Treat the opcode as a no-op and carry on.
+    Skip,
+    // Return the error with the PC still on the offending instruction, so a
+    // debugger can inspect or patch it and resume.
+    Trap
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    UnknownOpcode,
+    StackOverflow,
+    StackUnderflow,
+    MemoryOutOfBounds(u32)
+}
+
+// A fault raised while executing. `pc` is the address the opcode was fetched
+// from, `opcode` the raw instruction word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExecError {
+    pub pc: u32,
+    pub opcode: u32,
+    pub kind: ErrorKind
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnknownOpcode => write!(f, "unknown opcode {:04X} at {:03X}", self.opcode, self.pc),
+            ErrorKind::StackOverflow => write!(f, "stack overflow calling from {:03X} ({:04X})", self.pc, self.opcode),
+            ErrorKind::StackUnderflow => write!(f, "return without a call at {:03X} ({:04X})", self.pc, self.opcode),
+            ErrorKind::MemoryOutOfBounds(address) => write!(f, "memory access at {:#X} out of bounds at {:03X} ({:04X})", address, self.pc, self.opcode)
+        }
+    }
+}
+
+impl Error for ExecError {}
@@ -0,0 +1,7 @@
+// Everything that needs SDL: the window, the beep, translating host keys to
+// the CHIP-8 keypad, gamepads and the debugger prompt.
+pub mod audio;
+pub mod display;
+pub mod gamepad;
+pub mod keypad;
+pub mod prompt;
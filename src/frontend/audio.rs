@@ -0,0 +1,127 @@
+use std::sync::Mutex;
+
+use sdl::audio::{self, AudioFormat, Channels, DesiredAudioSpec};
+
+use emulator::audio::{AudioSink, Sound, Tone, ToneSettings};
+
+// SDL calls the audio callback on its own thread and gives it no state, so
+// the tone it plays from lives here.
+static OUTPUT: Mutex<Option<Output>> = Mutex::new(None);
+
+struct Output {
+    tone: Tone,
+    format: AudioFormat,
+    channels: usize,
+    samples: Vec<i16>
+}
+
+// The SDL audio device, open for as long as this lives. Only one can be open
+// at a time.
+pub struct SdlAudio;
+
+impl SdlAudio {
+    pub fn open(settings: ToneSettings) -> Result<SdlAudio, String> {
+        let desired = DesiredAudioSpec {
+            freq: 44100,
+            format: AudioFormat::S16LsbAudioFormat,
+            channels: Channels::Mono,
+            // About 12 ms, so a beep starts within a frame of the timer.
+            samples: 512,
+            callback: fill
+        };
+        let obtained = audio::open(desired).map_err(|()| format!("could not open audio: {}", sdl::get_error()))?;
+        if obtained.format != AudioFormat::S16LsbAudioFormat && obtained.format != AudioFormat::U8AudioFormat {
+            audio::close();
+            return Err(String::from("could not open audio: no 8 or 16-bit output"));
+        }
+        let channels = if obtained.channels == Channels::Stereo { 2 } else { 1 };
+        *lock() = Some(Output { tone: Tone::new(settings, obtained.freq as u32), format: obtained.format, channels, samples: Vec::new() });
+        audio::pause(false);
+        Ok(SdlAudio)
+    }
+
+    // A sink for the machine that plays its beep on this device.
+    pub fn sink(&self) -> SdlSink {
+        SdlSink
+    }
+
+    // Silences the beep until the next frame runs, for when emulation stops
+    // with the sound timer still going.
+    pub fn stop(&self) {
+        set_playing(false);
+    }
+
+    // Returns whether the beep is muted now.
+    pub fn toggle_mute(&self) -> bool {
+        match lock().as_mut() {
+            Some(output) => {
+                let muted = !output.tone.is_muted();
+                output.tone.set_muted(muted);
+                muted
+            },
+            None => true
+        }
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        if let Some(output) = lock().as_mut() {
+            output.tone.set_muted(muted);
+        }
+    }
+}
+
+impl Drop for SdlAudio {
+    fn drop(&mut self) {
+        audio::close();
+        *lock() = None;
+    }
+}
+
+pub struct SdlSink;
+
+impl AudioSink for SdlSink {
+    fn frame(&mut self, sound: Sound) {
+        if let Some(output) = lock().as_mut() {
+            output.tone.set_sound(sound);
+        }
+    }
+}
+
+fn set_playing(playing: bool) {
+    if let Some(output) = lock().as_mut() {
+        output.tone.set_playing(playing);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Output>> {
+    // A panic while holding the lock leaves nothing half written that
+    // matters for sound, so a poisoned lock is used as it is.
+    OUTPUT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn fill(buffer: &mut [u8]) {
+    let mut guard = lock();
+    let output = match guard.as_mut() {
+        Some(output) => output,
+        None => {
+            for byte in buffer.iter_mut() {
+                *byte = 0;
+            }
+            return;
+        }
+    };
+    let width = if output.format == AudioFormat::U8AudioFormat { 1 } else { 2 };
+    let frames = buffer.len() / (width * output.channels);
+    output.samples.resize(frames, 0);
+    output.tone.fill(&mut output.samples);
+
+    for (frame, &sample) in buffer.chunks_mut(width * output.channels).zip(output.samples.iter()) {
+        for channel in frame.chunks_mut(width) {
+            if width == 1 {
+                channel[0] = ((sample >> 8) + 128) as u8;
+            } else {
+                channel.copy_from_slice(&sample.to_le_bytes());
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use sdl::video;
+use sdl::Rect;
+
+use emulator::display::{DisplayBackend, Framebuffer};
+
+// Colours for the four XO-CHIP plane combinations, using Octo's defaults:
+// background, first plane, second plane, both planes.
+const PALETTE: [(u8, u8, u8); 4] = [
+    (0x00, 0x00, 0x00),
+    (0xFF, 0xFF, 0xFF),
+    (0xAA, 0xAA, 0xAA),
+    (0x55, 0x55, 0x55),
+];
+
+pub struct SdlDisplay {
+    screen: video::Surface,
+    scale: isize
+}
+
+impl SdlDisplay {
+    // `scale` must be even, so that high resolution pixels, half as big,
+    // still cover the window exactly. The command line checks it.
+    pub fn new(scale: isize) -> SdlDisplay {
+        SdlDisplay {
+            screen: video::set_video_mode(64*scale, 32*scale, 8,
+                                          &[video::SurfaceFlag::HWSurface],
+                                          &[video::VideoFlag::DoubleBuf]).unwrap(),
+            scale
+        }
+    }
+}
+
+impl DisplayBackend for SdlDisplay {
+    // The window keeps its size; in high resolution every pixel is half as big.
+    fn present(&mut self, gfx: &Framebuffer, width: usize, height: usize) {
+        let cell_w = self.scale * 64 / width as isize;
+        let cell_h = self.scale * 32 / height as isize;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (r, g, b) = PALETTE[gfx[y][x] as usize & 3];
+                let rect = Rect { x: (x as isize * cell_w) as i16, y: (y as isize * cell_h) as i16, w: cell_w as u16, h: cell_h as u16 };
+                self.screen.fill_rect(Some(rect), video::RGB(r, g, b));
+            }
+        }
+
+        self.screen.flip();
+    }
+}
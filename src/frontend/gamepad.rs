@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use sdl::joy::{self, Joystick};
+
+use emulator::input::Input;
+use emulator::keymap::{Device, Keypad};
+
+// SDL 1.2 only looks for joysticks when its joystick subsystem starts and
+// sends no event when one comes or goes, so finding new pads means restarting
+// it. That stalls for a moment, so it happens every few seconds only while no
+// pad is open, and otherwise when asked for with a hotkey. Pad state is polled
+// every frame rather than taken from events, which also picks up whatever is
+// held across a restart.
+const RESCAN: Duration = Duration::from_secs(3);
+
+// SDL_HAT_UP, SDL_HAT_RIGHT, SDL_HAT_DOWN and SDL_HAT_LEFT.
+const HAT_BITS: [(u8, &str); 4] = [(1, "up"), (2, "right"), (4, "down"), (8, "left")];
+
+pub struct Gamepads {
+    pads: Vec<Joystick>,
+    // SDL's index and name for each open pad. Identical pads share a name,
+    // so the index tells them apart.
+    names: Vec<(usize, String)>,
+    scanned: Instant,
+    rescan: bool
+}
+
+impl Gamepads {
+    pub fn open() -> Gamepads {
+        let mut gamepads = Gamepads { pads: Vec::new(), names: Vec::new(), scanned: Instant::now(), rescan: false };
+        gamepads.scan();
+        gamepads
+    }
+
+    fn scan(&mut self) {
+        self.pads.clear();
+        self.scanned = Instant::now();
+        let mut names = Vec::new();
+        for index in 0..joy::get_num_joysticks() {
+            match Joystick::open(index) {
+                Ok(pad) => {
+                    names.push((index as usize, joy::get_joystick_name(index)));
+                    self.pads.push(pad);
+                },
+                Err(e) => eprintln!("emulator: could not open joystick {}: {}", index, e)
+            }
+        }
+        for (index, name) in names.iter().filter(|pad| !self.names.contains(pad)) {
+            eprintln!("emulator: gamepad {} connected: {}", index, name);
+        }
+        for (index, name) in self.names.iter().filter(|pad| !names.contains(pad)) {
+            eprintln!("emulator: gamepad {} disconnected: {}", index, name);
+        }
+        self.names = names;
+    }
+
+    // Looks for pads plugged in or out at the next poll.
+    pub fn rescan(&mut self) {
+        self.rescan = true;
+    }
+
+    // Presses the keypad keys for whatever is held on the pads, looking for
+    // pads plugged in or out when it is time to.
+    pub fn poll(&mut self, keypad: &mut Keypad, input: &mut Input) {
+        if self.rescan || (self.pads.is_empty() && self.scanned.elapsed() >= RESCAN) {
+            self.rescan = false;
+            // Pads can come back in a different order, so nothing held is
+            // carried over; whatever is still held is pressed again below,
+            // before the machine runs.
+            for pad in 0..self.pads.len() {
+                keypad.release(input, Device::Pad(pad));
+            }
+            // The pads must be closed before the subsystem goes away.
+            self.pads.clear();
+            sdl::quit_subsystem(&[sdl::InitFlag::Joystick]);
+            sdl::init_subsystem(&[sdl::InitFlag::Joystick]);
+            self.scan();
+        }
+        joy::update_joysticks();
+        for (index, pad) in self.pads.iter().enumerate() {
+            for button in 0..pad.get_num_buttons() {
+                keypad.button(input, index, button as usize, pad.get_button(button) != 0);
+            }
+            for axis in 0..pad.get_num_axes() {
+                keypad.axis(input, index, axis as usize, pad.get_axis(axis));
+            }
+            // Every hat on a pad counts as the same one.
+            let bits = (0..pad.get_num_hats()).fold(0, |bits, hat| bits | pad.get_hat(hat));
+            let directions: Vec<&str> = HAT_BITS.iter().filter(|(bit, _)| bits & bit != 0).map(|&(_, name)| name).collect();
+            keypad.hat(input, index, &directions);
+        }
+    }
+}
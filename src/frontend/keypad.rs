@@ -0,0 +1,33 @@
+use sdl::event::Key;
+
+// The name a keymap uses for an SDL key; see emulator::keymap::HOST_KEYS.
+pub fn key_name(key: Key) -> Option<&'static str> {
+  let name = match key {
+    Key::Num0 => "0", Key::Num1 => "1", Key::Num2 => "2", Key::Num3 => "3", Key::Num4 => "4",
+    Key::Num5 => "5", Key::Num6 => "6", Key::Num7 => "7", Key::Num8 => "8", Key::Num9 => "9",
+    Key::A => "a", Key::B => "b", Key::C => "c", Key::D => "d", Key::E => "e", Key::F => "f",
+    Key::G => "g", Key::H => "h", Key::I => "i", Key::J => "j", Key::K => "k", Key::L => "l",
+    Key::M => "m", Key::N => "n", Key::O => "o", Key::P => "p", Key::Q => "q", Key::R => "r",
+    Key::S => "s", Key::T => "t", Key::U => "u", Key::V => "v", Key::W => "w", Key::X => "x",
+    Key::Y => "y", Key::Z => "z",
+    Key::Kp0 => "kp0", Key::Kp1 => "kp1", Key::Kp2 => "kp2", Key::Kp3 => "kp3", Key::Kp4 => "kp4",
+    Key::Kp5 => "kp5", Key::Kp6 => "kp6", Key::Kp7 => "kp7", Key::Kp8 => "kp8", Key::Kp9 => "kp9",
+    Key::KpPeriod => "kpperiod", Key::KpDivide => "kpdivide", Key::KpMultiply => "kpmultiply",
+    Key::KpMinus => "kpminus", Key::KpPlus => "kpplus", Key::KpEnter => "kpenter",
+    Key::Up => "up", Key::Down => "down", Key::Left => "left", Key::Right => "right",
+    Key::Insert => "insert", Key::Delete => "delete", Key::Home => "home", Key::End => "end",
+    Key::PageUp => "pageup", Key::PageDown => "pagedown",
+    Key::Space => "space", Key::Return => "return", Key::Tab => "tab",
+    Key::LShift => "lshift", Key::RShift => "rshift", Key::LCtrl => "lctrl", Key::RCtrl => "rctrl",
+    Key::LAlt => "lalt", Key::RAlt => "ralt",
+    Key::Comma => "comma", Key::Period => "period", Key::Slash => "slash", Key::Semicolon => "semicolon",
+    Key::Colon => "colon", Key::Quote => "quote", Key::Quotedbl => "quotedbl", Key::Minus => "minus",
+    Key::Underscore => "underscore", Key::Equals => "equals", Key::LeftBracket => "leftbracket",
+    Key::RightBracket => "rightbracket", Key::LeftParen => "leftparen", Key::RightParen => "rightparen",
+    Key::Backslash => "backslash", Key::Backquote => "backquote", Key::Ampersand => "ampersand",
+    Key::Exclaim => "exclaim", Key::Less => "less", Key::Greater => "greater", Key::Asterisk => "asterisk",
+    Key::Dollar => "dollar", Key::Caret => "caret",
+    _ => return None
+  };
+  Some(name)
+}
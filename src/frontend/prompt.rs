@@ -0,0 +1,49 @@
+use std::io::{self, BufRead, Write};
+
+use emulator::chip8::Chip8;
+use emulator::debugger::{self, Command, Debugger};
+
+// Reads debugger commands from stdin until one resumes emulation, and returns
+// false if the user quits instead. The window is redrawn after every command,
+// so stepping shows each change, but it handles no events in the meantime.
+// An empty line repeats the last command, like gdb.
+pub fn run(chip: &mut Chip8, debugger: &mut Debugger) -> bool {
+    println!("{}", debugger::listing(chip, chip.get_pc(), 1));
+    let stdin = io::stdin();
+    let mut last = None;
+    loop {
+        print!("(chip8) ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                println!("\nstdin closed, resuming");
+                return true;
+            },
+            Ok(_) => {}
+        }
+        let command = match (line.trim().is_empty(), &last) {
+            (true, Some(command)) => Ok(Command::clone(command)),
+            (true, None) => continue,
+            (false, _) => debugger::parse_command(&line)
+        };
+        match command {
+            Ok(command) => {
+                last = Some(command.clone());
+                let outcome = debugger.run_command(chip, command);
+                if !outcome.output.is_empty() {
+                    println!("{}", outcome.output);
+                }
+                if outcome.quit {
+                    return false;
+                }
+                if outcome.resume {
+                    return true;
+                }
+                chip.display.draw_screen();
+            },
+            Err(e) => println!("{}", e)
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+mod history_tests;
+
+use crate::chip8::Chip8;
+use crate::savestate::MachineState;
+
+// Lets the debugger run the machine backwards. Every `interval` cycles a full
+// snapshot is taken, and every change to the keypad is logged with the cycle
+// it happened on. An earlier cycle is reached by restoring the last snapshot
+// before it and running forward again with the same key presses.
+//
+// That only lands on the same state if the machine behaves the same way the
+// second time round. It does: CXNN draws from the machine's own seeded
+// generator and the timers tick on emulated cycles, not the wall clock, and
+// both are part of every snapshot. The keypad is the only input from outside.
+// The logged keys are only used while replaying: once there, the keypad is
+// left as the player is holding it now.
+pub struct History {
+    interval: u64,
+    capacity: usize,
+    checkpoints: VecDeque<MachineState>,
+    inputs: VecDeque<(u64, u16)>,
+    last_keys: Option<u16>
+}
+
+impl History {
+    // Keeps up to `capacity` snapshots taken `interval` cycles apart, so
+    // roughly `interval * capacity` cycles can be undone.
+    pub fn new(interval: u64, capacity: usize) -> History {
+        History {
+            interval: interval.max(1),
+            capacity: capacity.max(1),
+            checkpoints: VecDeque::new(),
+            inputs: VecDeque::new(),
+            last_keys: None
+        }
+    }
+
+    // Call before every cycle while history is wanted.
+    pub fn record(&mut self, chip: &Chip8) {
+        let cycle = chip.get_cycles();
+        // After going backwards the old future is gone.
+        while self.checkpoints.back().is_some_and(|state| state.cycles > cycle) {
+            self.checkpoints.pop_back();
+        }
+        while self.inputs.back().is_some_and(|&(at, _)| at > cycle) {
+            self.inputs.pop_back();
+            self.last_keys = self.inputs.back().map(|&(_, keys)| keys);
+        }
+
+        let keys = chip.input.get_mask();
+        if self.last_keys != Some(keys) {
+            self.inputs.push_back((cycle, keys));
+            self.last_keys = Some(keys);
+        }
+
+        let due = match self.checkpoints.back() {
+            Some(state) => cycle >= state.cycles + self.interval,
+            None => true
+        };
+        if due {
+            self.checkpoints.push_back(chip.snapshot());
+            if self.checkpoints.len() > self.capacity {
+                self.checkpoints.pop_front();
+                let oldest = self.checkpoints[0].cycles;
+                while self.inputs.len() > 1 && self.inputs[1].0 <= oldest {
+                    self.inputs.pop_front();
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+        self.inputs.clear();
+        self.last_keys = None;
+    }
+
+    // The earliest cycle that can still be reached.
+    pub fn oldest_cycle(&self) -> Option<u64> {
+        self.checkpoints.front().map(|state| state.cycles)
+    }
+
+    // Goes back `count` cycles, or as far as the history reaches. Returns how
+    // many cycles were actually undone.
+    pub fn step_back(&mut self, chip: &mut Chip8, count: u64) -> u64 {
+        let now = chip.get_cycles();
+        let oldest = match self.oldest_cycle() {
+            Some(oldest) if oldest <= now => oldest,
+            _ => return 0
+        };
+        let target = now.saturating_sub(count).max(oldest);
+        self.seek(chip, target);
+        now - chip.get_cycles()
+    }
+
+    // Finds the most recent earlier cycle at which `stop` held, checked before
+    // the instruction at that cycle ran, and puts the machine there. Leaves the
+    // machine where it was and returns None if there is no such cycle.
+    pub fn reverse_until<F: Fn(&Chip8) -> bool>(&mut self, chip: &mut Chip8, stop: F) -> Option<u64> {
+        let now = chip.get_cycles();
+        let live_keys = chip.input.get_mask();
+        let mut end = now;
+        for i in (0..self.checkpoints.len()).rev() {
+            let start = self.checkpoints[i].cycles;
+            if start >= end {
+                continue;
+            }
+            let mut found = None;
+            chip.restore(&self.checkpoints[i]);
+            let (tracer, audio) = (chip.take_tracer(), chip.take_audio_sink());
+            while chip.get_cycles() < end {
+                if stop(chip) {
+                    found = Some(chip.get_cycles());
+                }
+                if !self.replay_cycle(chip) {
+                    break;
+                }
+            }
+            if let Some(tracer) = tracer {
+                chip.set_tracer(tracer);
+            }
+            if let Some(audio) = audio {
+                chip.set_audio_sink(audio);
+            }
+            if let Some(cycle) = found {
+                self.seek(chip, cycle);
+                chip.input.set_mask(live_keys);
+                return Some(cycle);
+            }
+            end = start;
+        }
+        self.seek(chip, now);
+        chip.input.set_mask(live_keys);
+        None
+    }
+
+    // Restores the last checkpoint at or before `target` and replays up to it.
+    fn seek(&self, chip: &mut Chip8, target: u64) {
+        let checkpoint = match self.checkpoints.iter().rev().find(|state| state.cycles <= target) {
+            Some(checkpoint) => checkpoint,
+            None => return
+        };
+        let live_keys = chip.input.get_mask();
+        chip.restore(checkpoint);
+        // The trace already has these instructions from the first time round,
+        // and their sound has already been played.
+        let (tracer, audio) = (chip.take_tracer(), chip.take_audio_sink());
+        while chip.get_cycles() < target {
+            if !self.replay_cycle(chip) {
+                break;
+            }
+        }
+        if let Some(tracer) = tracer {
+            chip.set_tracer(tracer);
+        }
+        if let Some(audio) = audio {
+            chip.set_audio_sink(audio);
+        }
+        chip.input.set_mask(live_keys);
+    }
+
+    fn replay_cycle(&self, chip: &mut Chip8) -> bool {
+        self.apply_keys(chip);
+        chip.cycle().is_ok() && !chip.has_exited()
+    }
+
+    // Sets the keypad to what it was at the machine's current cycle.
+    fn apply_keys(&self, chip: &mut Chip8) {
+        let cycle = chip.get_cycles();
+        if let Some(&(_, keys)) = self.inputs.iter().rev().find(|&&(at, _)| at <= cycle) {
+            chip.input.set_mask(keys);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod history_tests {
+    use crate::history::*;
+    use crate::chip8::{init_chip, Chip8};
+    use crate::debugger::{Command, Debugger};
+    use crate::savestate::MachineState;
+
+    // Counts V0 up, and V3 up as well on the cycles key 5 is held.
+    const COUNTER: [u8; 12] = [
+        0x70, 0x01, // 200: V0 += 1
+        0xE2, 0xA1, // 202: skip if key V2 is not pressed
+        0x73, 0x01, // 204: V3 += 1
+        0xF0, 0x29, // 206: I = font(V0)
+        0xD1, 0x15, // 208: draw at V1, V1
+        0x12, 0x00, // 20A: jump 200
+    ];
+
+    // The machine matches the state apart from the keypad, which going back
+    // leaves as it is held now.
+    fn same(expected: &MachineState, chip: &Chip8) -> bool {
+        MachineState { keys: expected.keys, ..chip.snapshot() } == *expected
+    }
+
+    fn set_up() -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(&COUNTER).unwrap();
+        chip.execute(0x6205).unwrap();
+        chip
+    }
+
+    #[test]
+    fn test_step_back_replays_input() {
+        let mut chip8 = set_up();
+        let mut history = History::new(7, 100);
+        let mut states = Vec::new();
+        for cycle in 0..120 {
+            if cycle == 30 { chip8.input.set_mask(1 << 5) }
+            if cycle == 61 { chip8.input.set_mask(0) }
+            states.push(chip8.snapshot());
+            history.record(&chip8);
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(120, chip8.get_cycles());
+
+        assert_eq!(1, history.step_back(&mut chip8, 1));
+        assert!(same(&states[119], &chip8));
+        assert_eq!(54, history.step_back(&mut chip8, 54));
+        assert!(same(&states[65], &chip8));
+        assert_eq!(20, history.step_back(&mut chip8, 20));
+        assert!(same(&states[45], &chip8));
+        assert_eq!(0, chip8.input.get_mask());
+        assert_eq!(45, history.step_back(&mut chip8, 1000));
+        assert!(same(&states[0], &chip8));
+        assert_eq!(0, history.step_back(&mut chip8, 1));
+    }
+
+    #[test]
+    fn test_capacity_and_new_future() {
+        let mut chip8 = set_up();
+        let mut history = History::new(10, 3);
+        for _ in 0..50 {
+            history.record(&chip8);
+            chip8.cycle().unwrap();
+        }
+        assert_eq!(Some(20), history.oldest_cycle());
+        assert_eq!(30, history.step_back(&mut chip8, 40));
+
+        // Running on from the past replaces what used to come after it.
+        chip8.input.set_mask(1 << 5);
+        for _ in 0..5 {
+            history.record(&chip8);
+            chip8.cycle().unwrap();
+        }
+        let state = chip8.snapshot();
+        history.step_back(&mut chip8, 3);
+        for _ in 0..3 {
+            history.record(&chip8);
+            chip8.cycle().unwrap();
+        }
+        assert!(state == chip8.snapshot());
+    }
+
+    /**
+     * Reverse continue stops on the latest match, not the first one.
+     */
+    #[test]
+    fn test_reverse_until() {
+        let mut chip8 = set_up();
+        let mut history = History::new(16, 100);
+        for _ in 0..200 {
+            history.record(&chip8);
+            chip8.cycle().unwrap();
+        }
+        let found = history.reverse_until(&mut chip8, |chip| chip.get_pc() == 0x208);
+        assert_eq!(Some(198), found);
+        assert_eq!(0x208, chip8.get_pc());
+        assert_eq!(Some(193), history.reverse_until(&mut chip8, |chip| chip.get_pc() == 0x208));
+
+        let before = chip8.snapshot();
+        assert_eq!(None, history.reverse_until(&mut chip8, |chip| chip.get_register(0) == 0xFF));
+        assert!(before == chip8.snapshot());
+    }
+
+    #[test]
+    fn test_debugger_commands() {
+        let mut chip8 = set_up();
+        let mut debugger = Debugger::new();
+        debugger.run_command(&mut chip8, Command::Step(30));
+        debugger.add_breakpoint(0x206);
+        debugger.run_command(&mut chip8, Command::Step(9));
+
+        let outcome = debugger.run_command(&mut chip8, Command::ReverseContinue(None));
+        assert_eq!("breakpoint at 206\n> 206  F029  LD F, V0", outcome.output);
+        assert_eq!(37, chip8.get_cycles());
+
+        let outcome = debugger.run_command(&mut chip8, Command::StepBack(2));
+        assert_eq!("> 200  7001  ADD V0, 0x01", outcome.output);
+        assert_eq!(35, chip8.get_cycles());
+
+        let outcome = debugger.run_command(&mut chip8, Command::StepBack(100));
+        assert_eq!("reached the start of the recorded history\n> 200  7001  ADD V0, 0x01", outcome.output);
+        assert_eq!(0, chip8.get_cycles());
+    }
+}
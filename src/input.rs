@@ -1,5 +1,6 @@
-use sdl::event::Key;
-
+// The hex keypad, keys 0x0 to 0xF. Frontends translate whatever the player
+// presses into these keys; nothing here knows about host keyboards.
+#[derive(Default)]
 pub struct Input {
   keys: [bool; 16]
 }
@@ -9,33 +10,35 @@ impl Input {
     Input { keys: [false; 16] }
   }
 
-  pub fn pressed(&mut self, index: usize) -> bool {
+  pub fn pressed(&self, index: usize) -> bool {
     self.keys[index]
   }
 
-  pub fn press(&mut self, key: Key, state: bool) {
-    match key {
-      Key::Num1 => self.set_key(0x1, state),
-      Key::Num2 => self.set_key(0x2, state),
-      Key::Num3 => self.set_key(0x3, state),
-      Key::Num4 => self.set_key(0xc, state),
-      Key::Q    => self.set_key(0x4, state),
-      Key::W    => self.set_key(0x5, state),
-      Key::E    => self.set_key(0x6, state),
-      Key::R    => self.set_key(0xd, state),
-      Key::A    => self.set_key(0x7, state),
-      Key::S    => self.set_key(0x8, state),
-      Key::D    => self.set_key(0x9, state),
-      Key::F    => self.set_key(0xe, state),
-      Key::Z    => self.set_key(0xa, state),
-      Key::X    => self.set_key(0x0, state),
-      Key::C    => self.set_key(0xb, state),
-      Key::V    => self.set_key(0xf, state),
-      _         => ()
+  pub fn set_key(&mut self, index: usize, state: bool) {
+    self.keys[index] = state;
+  }
+
+  // Keys past 0xF do not exist on the keypad and are ignored.
+  pub fn key_down(&mut self, key: usize) {
+    if key < self.keys.len() {
+      self.keys[key] = true;
     }
   }
 
-  fn set_key(&mut self, index: usize, state: bool) {
-    self.keys[index] = state;
+  pub fn key_up(&mut self, key: usize) {
+    if key < self.keys.len() {
+      self.keys[key] = false;
+    }
   }
-}
\ No newline at end of file
+
+  // All keys as a bit mask, key 0 in the lowest bit.
+  pub fn get_mask(&self) -> u16 {
+    self.keys.iter().enumerate().fold(0, |mask, (i, &down)| mask | ((down as u16) << i))
+  }
+
+  pub fn set_mask(&mut self, mask: u16) {
+    for (i, key) in self.keys.iter_mut().enumerate() {
+      *key = mask & (1 << i) != 0;
+    }
+  }
+}
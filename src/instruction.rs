@@ -0,0 +1,290 @@
+use std::error::Error;
+use std::fmt;
+mod decode_tests;
+
+use crate::platform::Platform;
+
+// One decoded CHIP-8 instruction. `x` and `y` are register indices, `nn` an
+// 8-bit immediate, `nnn` a 12-bit address and `n` a 4-bit nibble, named after
+// the usual opcode notation (e.g. 7XNN is AddImm { x, nn }).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Clear,                          // 00E0
+    Return,                         // 00EE
+    ScrollDown { n: u8 },           // 00CN  SUPER-CHIP
+    ScrollUp { n: u8 },             // 00DN  XO-CHIP
+    ScrollRight,                    // 00FB  SUPER-CHIP
+    ScrollLeft,                     // 00FC  SUPER-CHIP
+    Exit,                           // 00FD  SUPER-CHIP
+    LowRes,                         // 00FE  SUPER-CHIP
+    HighRes,                        // 00FF  SUPER-CHIP
+    Jump { nnn: u16 },              // 1NNN
+    Call { nnn: u16 },              // 2NNN
+    SkipEqImm { x: u8, nn: u8 },    // 3XNN
+    SkipNeImm { x: u8, nn: u8 },    // 4XNN
+    SkipEqReg { x: u8, y: u8 },     // 5XY0
+    StoreRange { x: u8, y: u8 },    // 5XY2  XO-CHIP
+    LoadRange { x: u8, y: u8 },     // 5XY3  XO-CHIP
+    LoadImm { x: u8, nn: u8 },      // 6XNN
+    AddImm { x: u8, nn: u8 },       // 7XNN
+    LoadReg { x: u8, y: u8 },       // 8XY0
+    Or { x: u8, y: u8 },            // 8XY1
+    And { x: u8, y: u8 },           // 8XY2
+    Xor { x: u8, y: u8 },           // 8XY3
+    AddReg { x: u8, y: u8 },        // 8XY4
+    SubReg { x: u8, y: u8 },        // 8XY5
+    ShiftRight { x: u8, y: u8 },    // 8XY6
+    SubNeg { x: u8, y: u8 },        // 8XY7
+    ShiftLeft { x: u8, y: u8 },     // 8XYE
+    SkipNeReg { x: u8, y: u8 },     // 9XY0
+    LoadI { nnn: u16 },             // ANNN
+    JumpOffset { nnn: u16 },        // BNNN
+    Random { x: u8, nn: u8 },       // CXNN
+    Draw { x: u8, y: u8, n: u8 },   // DXYN, DXY0 draws 16x16 on SUPER-CHIP
+    SkipKey { x: u8 },              // EX9E
+    SkipNotKey { x: u8 },           // EXA1
+    LoadILong,                      // F000 NNNN  XO-CHIP, the address is the next word
+    SelectPlanes { n: u8 },         // FN01  XO-CHIP
+    LoadAudio,                      // F002  XO-CHIP
+    LoadDelay { x: u8 },            // FX07
+    WaitKey { x: u8 },              // FX0A
+    SetDelay { x: u8 },             // FX15
+    SetSound { x: u8 },             // FX18
+    AddI { x: u8 },                 // FX1E
+    LoadFont { x: u8 },             // FX29
+    LoadBigFont { x: u8 },          // FX30  SUPER-CHIP
+    SetPitch { x: u8 },             // FX3A  XO-CHIP
+    StoreBcd { x: u8 },             // FX33
+    StoreRegs { x: u8 },            // FX55
+    LoadRegs { x: u8 },             // FX65
+    StoreFlags { x: u8 },           // FX75  SUPER-CHIP
+    LoadFlags { x: u8 }             // FX85  SUPER-CHIP
+}
+
+impl Instruction {
+    // The first platform that has this instruction.
+    pub fn platform(&self) -> Platform {
+        match self {
+            Instruction::ScrollDown { .. } | Instruction::ScrollRight | Instruction::ScrollLeft |
+            Instruction::Exit | Instruction::LowRes | Instruction::HighRes |
+            Instruction::LoadBigFont { .. } | Instruction::StoreFlags { .. } |
+            Instruction::LoadFlags { .. } => Platform::SuperChip,
+            Instruction::ScrollUp { .. } | Instruction::StoreRange { .. } | Instruction::LoadRange { .. } |
+            Instruction::LoadILong | Instruction::SelectPlanes { .. } | Instruction::LoadAudio |
+            Instruction::SetPitch { .. } => Platform::XoChip,
+            _ => Platform::Chip8
+        }
+    }
+
+    // The opcode that decodes to this instruction. F000 gives just the first
+    // word; its address follows separately.
+    pub fn encode(&self) -> u16 {
+        let xy = |x: u8, y: u8| ((x as u16) << 8) | ((y as u16) << 4);
+        let xnn = |x: u8, nn: u8| ((x as u16) << 8) | nn as u16;
+        match *self {
+            Instruction::Clear => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::ScrollDown { n } => 0x00C0 | n as u16,
+            Instruction::ScrollUp { n } => 0x00D0 | n as u16,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::LowRes => 0x00FE,
+            Instruction::HighRes => 0x00FF,
+            Instruction::Jump { nnn } => 0x1000 | nnn,
+            Instruction::Call { nnn } => 0x2000 | nnn,
+            Instruction::SkipEqImm { x, nn } => 0x3000 | xnn(x, nn),
+            Instruction::SkipNeImm { x, nn } => 0x4000 | xnn(x, nn),
+            Instruction::SkipEqReg { x, y } => 0x5000 | xy(x, y),
+            Instruction::StoreRange { x, y } => 0x5002 | xy(x, y),
+            Instruction::LoadRange { x, y } => 0x5003 | xy(x, y),
+            Instruction::LoadImm { x, nn } => 0x6000 | xnn(x, nn),
+            Instruction::AddImm { x, nn } => 0x7000 | xnn(x, nn),
+            Instruction::LoadReg { x, y } => 0x8000 | xy(x, y),
+            Instruction::Or { x, y } => 0x8001 | xy(x, y),
+            Instruction::And { x, y } => 0x8002 | xy(x, y),
+            Instruction::Xor { x, y } => 0x8003 | xy(x, y),
+            Instruction::AddReg { x, y } => 0x8004 | xy(x, y),
+            Instruction::SubReg { x, y } => 0x8005 | xy(x, y),
+            Instruction::ShiftRight { x, y } => 0x8006 | xy(x, y),
+            Instruction::SubNeg { x, y } => 0x8007 | xy(x, y),
+            Instruction::ShiftLeft { x, y } => 0x800E | xy(x, y),
+            Instruction::SkipNeReg { x, y } => 0x9000 | xy(x, y),
+            Instruction::LoadI { nnn } => 0xA000 | nnn,
+            Instruction::JumpOffset { nnn } => 0xB000 | nnn,
+            Instruction::Random { x, nn } => 0xC000 | xnn(x, nn),
+            Instruction::Draw { x, y, n } => 0xD000 | xy(x, y) | n as u16,
+            Instruction::SkipKey { x } => 0xE09E | xnn(x, 0),
+            Instruction::SkipNotKey { x } => 0xE0A1 | xnn(x, 0),
+            Instruction::LoadILong => 0xF000,
+            Instruction::SelectPlanes { n } => 0xF001 | xnn(n, 0),
+            Instruction::LoadAudio => 0xF002,
+            Instruction::LoadDelay { x } => 0xF007 | xnn(x, 0),
+            Instruction::WaitKey { x } => 0xF00A | xnn(x, 0),
+            Instruction::SetDelay { x } => 0xF015 | xnn(x, 0),
+            Instruction::SetSound { x } => 0xF018 | xnn(x, 0),
+            Instruction::AddI { x } => 0xF01E | xnn(x, 0),
+            Instruction::LoadFont { x } => 0xF029 | xnn(x, 0),
+            Instruction::LoadBigFont { x } => 0xF030 | xnn(x, 0),
+            Instruction::SetPitch { x } => 0xF03A | xnn(x, 0),
+            Instruction::StoreBcd { x } => 0xF033 | xnn(x, 0),
+            Instruction::StoreRegs { x } => 0xF055 | xnn(x, 0),
+            Instruction::LoadRegs { x } => 0xF065 | xnn(x, 0),
+            Instruction::StoreFlags { x } => 0xF075 | xnn(x, 0),
+            Instruction::LoadFlags { x } => 0xF085 | xnn(x, 0)
+        }
+    }
+}
+
+// Mnemonics in the style of Cowgod's technical reference, which most CHIP-8
+// documentation and disassemblers follow. F000 prints without its address,
+// which is the word after it.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Clear => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::ScrollDown { n } => write!(f, "SCD {}", n),
+            Instruction::ScrollUp { n } => write!(f, "SCU {}", n),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::LowRes => write!(f, "LOW"),
+            Instruction::HighRes => write!(f, "HIGH"),
+            Instruction::Jump { nnn } => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call { nnn } => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SkipEqImm { x, nn } => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipNeImm { x, nn } => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipEqReg { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::StoreRange { x, y } => write!(f, "SAVE V{:X}-V{:X}", x, y),
+            Instruction::LoadRange { x, y } => write!(f, "LOAD V{:X}-V{:X}", x, y),
+            Instruction::LoadImm { x, nn } => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            Instruction::AddImm { x, nn } => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            Instruction::LoadReg { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddReg { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::SubReg { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubNeg { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNeReg { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI { nnn } => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JumpOffset { nnn } => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Random { x, nn } => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Instruction::Draw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKey { x } => write!(f, "SKP V{:X}", x),
+            Instruction::SkipNotKey { x } => write!(f, "SKNP V{:X}", x),
+            Instruction::LoadILong => write!(f, "LD I, long"),
+            Instruction::SelectPlanes { n } => write!(f, "PLANE {}", n),
+            Instruction::LoadAudio => write!(f, "AUDIO"),
+            Instruction::LoadDelay { x } => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKey { x } => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay { x } => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound { x } => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI { x } => write!(f, "ADD I, V{:X}", x),
+            Instruction::LoadFont { x } => write!(f, "LD F, V{:X}", x),
+            Instruction::LoadBigFont { x } => write!(f, "LD HF, V{:X}", x),
+            Instruction::SetPitch { x } => write!(f, "PITCH V{:X}", x),
+            Instruction::StoreBcd { x } => write!(f, "LD B, V{:X}", x),
+            Instruction::StoreRegs { x } => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegs { x } => write!(f, "LD V{:X}, [I]", x),
+            Instruction::StoreFlags { x } => write!(f, "LD R, V{:X}", x),
+            Instruction::LoadFlags { x } => write!(f, "LD V{:X}, R", x)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeError {
+    pub opcode: u16
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown opcode {:04X}", self.opcode)
+    }
+}
+
+impl Error for DecodeError {}
+
+pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+    let x = ((opcode & 0x0F00) >> 8) as u8;
+    let y = ((opcode & 0x00F0) >> 4) as u8;
+    let n = (opcode & 0x000F) as u8;
+    let nn = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    let instruction = match opcode & 0xF000 {
+        0x0000 => match opcode {
+            // 0000 has always cleared the screen in this interpreter, which
+            // keeps runaway programs in zeroed memory from faulting.
+            0x0000 | 0x00E0 => Instruction::Clear,
+            0x00EE => Instruction::Return,
+            0x00C0..=0x00CF => Instruction::ScrollDown { n },
+            0x00D0..=0x00DF => Instruction::ScrollUp { n },
+            0x00FB => Instruction::ScrollRight,
+            0x00FC => Instruction::ScrollLeft,
+            0x00FD => Instruction::Exit,
+            0x00FE => Instruction::LowRes,
+            0x00FF => Instruction::HighRes,
+            _ => return Err(DecodeError { opcode })
+        },
+        0x1000 => Instruction::Jump { nnn },
+        0x2000 => Instruction::Call { nnn },
+        0x3000 => Instruction::SkipEqImm { x, nn },
+        0x4000 => Instruction::SkipNeImm { x, nn },
+        0x5000 => match n {
+            0x0 => Instruction::SkipEqReg { x, y },
+            0x2 => Instruction::StoreRange { x, y },
+            0x3 => Instruction::LoadRange { x, y },
+            _ => return Err(DecodeError { opcode })
+        },
+        0x6000 => Instruction::LoadImm { x, nn },
+        0x7000 => Instruction::AddImm { x, nn },
+        0x8000 => match n {
+            0x0 => Instruction::LoadReg { x, y },
+            0x1 => Instruction::Or { x, y },
+            0x2 => Instruction::And { x, y },
+            0x3 => Instruction::Xor { x, y },
+            0x4 => Instruction::AddReg { x, y },
+            0x5 => Instruction::SubReg { x, y },
+            0x6 => Instruction::ShiftRight { x, y },
+            0x7 => Instruction::SubNeg { x, y },
+            0xE => Instruction::ShiftLeft { x, y },
+            _ => return Err(DecodeError { opcode })
+        },
+        0x9000 if n == 0 => Instruction::SkipNeReg { x, y },
+        0xA000 => Instruction::LoadI { nnn },
+        0xB000 => Instruction::JumpOffset { nnn },
+        0xC000 => Instruction::Random { x, nn },
+        0xD000 => Instruction::Draw { x, y, n },
+        0xE000 => match nn {
+            0x9E => Instruction::SkipKey { x },
+            0xA1 => Instruction::SkipNotKey { x },
+            _ => return Err(DecodeError { opcode })
+        },
+        0xF000 => match nn {
+            0x00 if x == 0 => Instruction::LoadILong,
+            0x01 => Instruction::SelectPlanes { n: x },
+            0x02 if x == 0 => Instruction::LoadAudio,
+            0x07 => Instruction::LoadDelay { x },
+            0x0A => Instruction::WaitKey { x },
+            0x15 => Instruction::SetDelay { x },
+            0x18 => Instruction::SetSound { x },
+            0x1E => Instruction::AddI { x },
+            0x29 => Instruction::LoadFont { x },
+            0x30 => Instruction::LoadBigFont { x },
+            0x3A => Instruction::SetPitch { x },
+            0x33 => Instruction::StoreBcd { x },
+            0x55 => Instruction::StoreRegs { x },
+            0x65 => Instruction::LoadRegs { x },
+            0x75 => Instruction::StoreFlags { x },
+            0x85 => Instruction::LoadFlags { x },
+            _ => return Err(DecodeError { opcode })
+        },
+        _ => return Err(DecodeError { opcode })
+    };
+    Ok(instruction)
+}
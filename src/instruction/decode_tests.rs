@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod decode_tests {
+    use crate::instruction::*;
+
+    #[test]
+    fn test_decode_operands() {
+        assert_eq!(Ok(Instruction::Jump { nnn: 0xDAE }), decode(0x1DAE));
+        assert_eq!(Ok(Instruction::SkipEqImm { x: 0x3, nn: 0x64 }), decode(0x3364));
+        assert_eq!(Ok(Instruction::SubNeg { x: 0xA, y: 0xB }), decode(0x8AB7));
+        assert_eq!(Ok(Instruction::Draw { x: 0x1, y: 0x2, n: 0x5 }), decode(0xD125));
+        assert_eq!(Ok(Instruction::LoadRegs { x: 0xF }), decode(0xFF65));
+    }
+
+    #[test]
+    fn test_decode_system_instructions() {
+        assert_eq!(Ok(Instruction::Clear), decode(0x00E0));
+        assert_eq!(Ok(Instruction::Clear), decode(0x0000));
+        assert_eq!(Ok(Instruction::Return), decode(0x00EE));
+        assert_eq!(Err(DecodeError { opcode: 0x0123 }), decode(0x0123));
+    }
+
+    #[test]
+    fn test_decode_superchip_instructions() {
+        assert_eq!(Ok(Instruction::ScrollDown { n: 0x7 }), decode(0x00C7));
+        assert_eq!(Ok(Instruction::HighRes), decode(0x00FF));
+        assert_eq!(Ok(Instruction::LoadBigFont { x: 0x4 }), decode(0xF430));
+        assert_eq!(Platform::SuperChip, Instruction::LoadFlags { x: 0 }.platform());
+        assert_eq!(Platform::Chip8, Instruction::Draw { x: 0, y: 0, n: 0 }.platform());
+    }
+
+    #[test]
+    fn test_decode_xochip_instructions() {
+        assert_eq!(Ok(Instruction::LoadILong), decode(0xF000));
+        assert_eq!(Ok(Instruction::SelectPlanes { n: 0x3 }), decode(0xF301));
+        assert_eq!(Ok(Instruction::StoreRange { x: 0x1, y: 0x4 }), decode(0x5142));
+        assert_eq!(Ok(Instruction::ScrollUp { n: 0x2 }), decode(0x00D2));
+        assert_eq!(Err(DecodeError { opcode: 0xF102 }), decode(0xF102));
+        assert_eq!(Platform::XoChip, Instruction::SetPitch { x: 0 }.platform());
+    }
+
+    #[test]
+    fn test_mnemonics() {
+        assert_eq!("LD V0, 0x15", decode(0x6015).unwrap().to_string());
+        assert_eq!("CALL 0x2A4", decode(0x22A4).unwrap().to_string());
+        assert_eq!("DRW VA, VB, 5", decode(0xDAB5).unwrap().to_string());
+        assert_eq!("LD [I], VF", decode(0xFF55).unwrap().to_string());
+        assert_eq!("SAVE V1-V4", decode(0x5142).unwrap().to_string());
+    }
+
+    /**
+     * 5XY0 and 9XY0 only exist with a zero low nibble, and the 8XYN, EXNN and
+     * FXNN groups only define some of their sub-opcodes.
+    */
+    #[test]
+    fn test_decode_unknown_opcodes() {
+        for opcode in [0x5121, 0x9124, 0x8128, 0x812F, 0xE19F, 0xF1FF, 0xF100].iter() {
+            assert_eq!(Err(DecodeError { opcode: *opcode }), decode(*opcode));
+        }
+    }
+
+    #[test]
+    fn test_decode_is_total() {
+        for opcode in 0..=0xFFFFu16 {
+            match decode(opcode) {
+                Ok(_) => {},
+                Err(e) => assert_eq!(opcode, e.opcode)
+            }
+        }
+    }
+
+    // 0000 is the one alias: it decodes to CLS, which encodes as 00E0.
+    #[test]
+    fn test_encode_round_trips() {
+        for opcode in 1..=0xFFFFu16 {
+            if let Ok(instruction) = decode(opcode) {
+                assert_eq!(opcode, instruction.encode(), "{}", instruction);
+            }
+        }
+    }
+}
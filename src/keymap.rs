@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+mod keymap_tests;
+
+use crate::input::Input;
+
+// Which host keys press which keypad keys. Host keys go by name ("q", "up",
+// "kp5", see HOST_KEYS), and so do gamepad inputs ("button0", "hat_up",
+// "axis1-", see is_pad_name); the frontend translates its own codes into these
+// names, so none of this depends on SDL.
+//
+// A keymap file has a section of defaults and any number of per-ROM sections
+// that override them:
+//
+//   # Comments start with #.
+//   mode = position        # or keysym
+//   layout = azerty        # qwerty (default), azerty, qwertz or dvorak
+//   threshold = 40         # how far, in percent, a stick is pushed to press
+//   5 = w, up, button2     # keypad key 5 on W, the up arrow and pad button 2
+//
+//   [rom pong]             # ROM file name, with or without extension,
+//   1 = up, hat_up         # or its 16-digit hash as shown in save state errors
+//   4 = down, hat_down
+//
+// The emulator's own hotkeys, such as P to pause and M to mute, give way to
+// any keyboard key the keymap binds.
+//
+// A keypad key given in a section loses the keyboard keys it had before it if
+// it is given keyboard keys, and its pad inputs if it is given pad inputs; an
+// empty list takes everything away. The host keys it is given stop pressing
+// anything else.
+//
+// Out of the box the pad's D-pad and first stick press 2/4/6/8, the usual
+// directions, and button 0 presses 5. Axis 0 is the stick's x axis and axis 1
+// its y axis, with "-" towards the left and the top.
+//
+// In keysym mode a name means the key the host reports, wherever it is on the
+// keyboard. In position mode, the default, a name means a position on a US
+// QWERTY keyboard, and the layout says which key the host reports there. That
+// way the built-in 1234/QWER/ASDF/ZXCV block stays a block on other layouts.
+// SDL 1.2 does not report scancodes, which is why the layout has to be named;
+// keys it cannot name, such as the accented digits of AZERTY, cannot be
+// mapped by position.
+
+pub const HOST_KEYS: &[&str] = &[
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "kp0", "kp1", "kp2", "kp3", "kp4", "kp5", "kp6", "kp7", "kp8", "kp9",
+    "kpperiod", "kpdivide", "kpmultiply", "kpminus", "kpplus", "kpenter",
+    "up", "down", "left", "right", "insert", "delete", "home", "end", "pageup", "pagedown",
+    "space", "return", "tab", "lshift", "rshift", "lctrl", "rctrl", "lalt", "ralt",
+    "comma", "period", "slash", "semicolon", "colon", "quote", "quotedbl", "minus", "underscore",
+    "equals", "leftbracket", "rightbracket", "leftparen", "rightparen", "backslash", "backquote",
+    "ampersand", "exclaim", "less", "greater", "asterisk", "dollar", "caret"
+];
+
+// Gamepad buttons, hat directions and half axes that a keymap can name.
+const PAD_BUTTONS: usize = 32;
+const PAD_AXES: usize = 16;
+const HAT_DIRECTIONS: &[&str] = &["up", "down", "left", "right"];
+
+pub fn is_pad_name(name: &str) -> bool {
+    let number = |digits: &str, limit: usize| {
+        digits.chars().all(|c| c.is_ascii_digit()) && digits.parse().is_ok_and(|n: usize| n < limit)
+    };
+    if let Some(button) = name.strip_prefix("button") {
+        return number(button, PAD_BUTTONS);
+    }
+    if let Some(direction) = name.strip_prefix("hat_") {
+        return HAT_DIRECTIONS.contains(&direction);
+    }
+    match name.strip_prefix("axis").and_then(|axis| axis.strip_suffix(['-', '+'])) {
+        Some(axis) => number(axis, PAD_AXES),
+        None => false
+    }
+}
+
+const DEFAULT_THRESHOLD: u8 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KeyMode {
+    Keysym,
+    #[default]
+    Position
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak
+}
+
+impl Layout {
+    pub fn from_name(name: &str) -> Option<Layout> {
+        match name.to_lowercase().as_str() {
+            "qwerty" => Some(Layout::Qwerty),
+            "azerty" => Some(Layout::Azerty),
+            "qwertz" => Some(Layout::Qwertz),
+            "dvorak" => Some(Layout::Dvorak),
+            _ => None
+        }
+    }
+
+    // The key this layout reports at the position of `position` on a US
+    // QWERTY keyboard, or None if it is not one with a name.
+    pub fn at(self, position: &str) -> Option<&str> {
+        let moved: &[(&str, Option<&'static str>)] = match self {
+            Layout::Qwerty => &[],
+            Layout::Azerty => &[
+                ("1", Some("ampersand")), ("2", None), ("3", Some("quotedbl")), ("4", Some("quote")),
+                ("5", Some("leftparen")), ("6", Some("minus")), ("7", None), ("8", Some("underscore")),
+                ("9", None), ("0", None), ("q", Some("a")), ("w", Some("z")), ("a", Some("q")),
+                ("semicolon", Some("m")), ("z", Some("w")), ("m", Some("comma")), ("comma", Some("semicolon")),
+                ("period", Some("colon")), ("slash", Some("exclaim"))
+            ],
+            Layout::Qwertz => &[("y", Some("z")), ("z", Some("y"))],
+            Layout::Dvorak => &[
+                ("q", Some("quote")), ("w", Some("comma")), ("e", Some("period")), ("r", Some("p")),
+                ("t", Some("y")), ("y", Some("f")), ("u", Some("g")), ("i", Some("c")), ("o", Some("r")),
+                ("p", Some("l")), ("leftbracket", Some("slash")), ("rightbracket", Some("equals")),
+                ("s", Some("o")), ("d", Some("e")), ("f", Some("u")), ("g", Some("i")), ("h", Some("d")),
+                ("j", Some("h")), ("k", Some("t")), ("l", Some("n")), ("semicolon", Some("s")),
+                ("quote", Some("minus")), ("z", Some("semicolon")), ("x", Some("q")), ("c", Some("j")),
+                ("v", Some("k")), ("b", Some("x")), ("n", Some("b")), ("comma", Some("w")),
+                ("period", Some("v")), ("slash", Some("z")), ("minus", Some("leftbracket")),
+                ("equals", Some("rightbracket"))
+            ]
+        };
+        match moved.iter().find(|(from, _)| *from == position) {
+            Some((_, to)) => *to,
+            None => Some(position)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct KeymapError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for KeymapError {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Section {
+    mode: Option<KeyMode>,
+    layout: Option<Layout>,
+    threshold: Option<u8>,
+    bindings: BTreeMap<usize, Vec<String>>
+}
+
+impl Section {
+    fn apply(&mut self, other: &Section) {
+        self.mode = other.mode.or(self.mode);
+        self.layout = other.layout.or(self.layout);
+        self.threshold = other.threshold.or(self.threshold);
+        for (&key, names) in &other.bindings {
+            // A host key can only press one keypad key, the one named last.
+            for hosts in self.bindings.values_mut() {
+                hosts.retain(|host| !names.contains(host));
+            }
+            let hosts = self.bindings.entry(key).or_default();
+            if names.is_empty() {
+                hosts.clear();
+            } else {
+                hosts.retain(|host| !names.iter().any(|name| is_pad_name(name) == is_pad_name(host)));
+            }
+            hosts.extend(names.iter().cloned());
+        }
+    }
+}
+
+// A parsed keymap file, before a ROM is chosen.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeymapConfig {
+    defaults: Section,
+    roms: Vec<(String, Section)>
+}
+
+impl KeymapConfig {
+    pub fn parse(text: &str) -> Result<KeymapConfig, KeymapError> {
+        let mut config = KeymapConfig::default();
+        let mut rom: Option<(String, Section)> = None;
+        for (i, raw) in text.lines().enumerate() {
+            let error = |message: String| KeymapError { line: i + 1, message };
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header.strip_suffix(']').and_then(|header| header.trim().strip_prefix("rom "))
+                    .map(str::trim).filter(|name| !name.is_empty())
+                    .ok_or_else(|| error(format!("expected a section like [rom NAME], got '{}'", line)))?;
+                config.roms.extend(rom.take());
+                rom = Some((name.to_lowercase(), Section::default()));
+                continue;
+            }
+            let (name, value) = line.split_once('=').ok_or_else(|| error(format!("expected NAME = VALUE, got '{}'", line)))?;
+            let (name, value) = (name.trim().to_lowercase(), value.trim().to_lowercase());
+            let section = match rom.as_mut() {
+                Some((_, section)) => section,
+                None => &mut config.defaults
+            };
+            match name.as_str() {
+                "mode" => {
+                    section.mode = Some(match value.as_str() {
+                        "keysym" => KeyMode::Keysym,
+                        "position" => KeyMode::Position,
+                        _ => return Err(error(format!("unknown mode '{}', expected keysym or position", value)))
+                    });
+                },
+                "layout" => {
+                    section.layout = Some(Layout::from_name(&value).ok_or_else(|| {
+                        error(format!("unknown layout '{}', expected qwerty, azerty, qwertz or dvorak", value))
+                    })?);
+                },
+                "threshold" => {
+                    section.threshold = Some(value.parse().ok().filter(|percent| (1..100).contains(percent)).ok_or_else(|| {
+                        error(format!("expected a threshold from 1 to 99 percent, got '{}'", value))
+                    })?);
+                },
+                _ => {
+                    let key = usize::from_str_radix(&name, 16).ok().filter(|&key| key < 16 && name.len() == 1)
+                        .ok_or_else(|| error(format!("expected a keypad key 0-F, mode or layout, got '{}'", name)))?;
+                    let mut hosts = Vec::new();
+                    for host in value.split(',').map(str::trim).filter(|host| !host.is_empty()) {
+                        if !HOST_KEYS.contains(&host) && !is_pad_name(host) {
+                            return Err(error(format!("unknown host key '{}'", host)));
+                        }
+                        hosts.push(host.to_string());
+                    }
+                    section.bindings.insert(key, hosts);
+                }
+            }
+        }
+        config.roms.extend(rom);
+        Ok(config)
+    }
+
+    // The keymap for a ROM, given its file name and hash: the built-in
+    // layout, then this file's defaults, then every section naming the ROM.
+    pub fn keymap_for(&self, rom_name: &str, rom_hash: u64) -> Keymap {
+        let file_name = rom_name.rsplit(['/', '\\']).next().unwrap_or(rom_name).to_lowercase();
+        let stem = match file_name.rfind('.') {
+            Some(dot) if dot > 0 => file_name[..dot].to_string(),
+            _ => file_name.clone()
+        };
+        let hash = format!("{:016x}", rom_hash);
+
+        let mut section = builtin();
+        section.apply(&self.defaults);
+        for (name, rom) in &self.roms {
+            if *name == file_name || *name == stem || *name == hash {
+                section.apply(rom);
+            }
+        }
+        Keymap::from_section(&section)
+    }
+}
+
+// 1234/QWER/ASDF/ZXCV for the 4x4 keypad 123C/456D/789E/A0BF, and the pad's
+// directions on 2/4/6/8 with button 0 on 5.
+fn builtin() -> Section {
+    let hosts = ["x", "1", "2", "3", "q", "w", "e", "a", "s", "d", "z", "c", "4", "r", "f", "v"];
+    let mut bindings: BTreeMap<usize, Vec<String>> =
+        hosts.iter().enumerate().map(|(key, host)| (key, vec![host.to_string()])).collect();
+    let pad = [
+        (0x2, "hat_up"), (0x2, "axis1-"), (0x8, "hat_down"), (0x8, "axis1+"),
+        (0x4, "hat_left"), (0x4, "axis0-"), (0x6, "hat_right"), (0x6, "axis0+"), (0x5, "button0")
+    ];
+    for (key, host) in pad {
+        bindings.entry(key).or_default().push(host.to_string());
+    }
+    Section { mode: Some(KeyMode::Position), layout: Some(Layout::Qwerty), threshold: Some(DEFAULT_THRESHOLD), bindings }
+}
+
+// Host key names to keypad keys, with positions already turned into the keys
+// the host reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    keys: HashMap<String, usize>,
+    // How far an axis goes, out of 32767, before its half presses a key.
+    threshold: i16
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::from_section(&builtin())
+    }
+}
+
+impl Keymap {
+    fn from_section(section: &Section) -> Keymap {
+        let layout = match section.mode.unwrap_or_default() {
+            KeyMode::Keysym => Layout::Qwerty,
+            KeyMode::Position => section.layout.unwrap_or_default()
+        };
+        let mut keys = HashMap::new();
+        for (&key, hosts) in &section.bindings {
+            for host in hosts {
+                if let Some(reported) = layout.at(host) {
+                    keys.insert(reported.to_string(), key);
+                }
+            }
+        }
+        let threshold = section.threshold.unwrap_or(DEFAULT_THRESHOLD) as i32 * i16::MAX as i32 / 100;
+        Keymap { keys, threshold: threshold as i16 }
+    }
+
+    // Binds another host key, replacing what it pressed before.
+    pub fn bind(&mut self, host: &str, key: usize) {
+        self.keys.insert(host.to_string(), key);
+    }
+
+    pub fn lookup(&self, host: &str) -> Option<usize> {
+        self.keys.get(host).cloned()
+    }
+
+    pub fn get_threshold(&self) -> i16 {
+        self.threshold
+    }
+}
+
+// What holds a host key down: the keyboard, or one of several pads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Device {
+    Keyboard,
+    Pad(usize)
+}
+
+// Turns host key presses into keypad presses. A keypad key stays down while
+// any host key bound to it is held, on any device.
+#[derive(Debug, Clone, Default)]
+pub struct Keypad {
+    keymap: Keymap,
+    held: HashSet<(Device, String)>
+}
+
+impl Keypad {
+    pub fn new(keymap: Keymap) -> Keypad {
+        Keypad { keymap, held: HashSet::new() }
+    }
+
+    pub fn get_keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    // A key on the keyboard. Returns false if it is not bound to anything.
+    pub fn press(&mut self, input: &mut Input, host: &str, down: bool) -> bool {
+        self.press_on(input, Device::Keyboard, host, down)
+    }
+
+    // Pressing a key already down, or letting go of one already up, changes
+    // nothing, so a frontend can report every input of a pad every frame.
+    pub fn press_on(&mut self, input: &mut Input, device: Device, host: &str, down: bool) -> bool {
+        let key = match self.keymap.lookup(host) {
+            Some(key) => key,
+            None => return false
+        };
+        let held = (device, host.to_string());
+        if down == self.held.contains(&held) {
+            return true;
+        }
+        if down {
+            self.held.insert(held);
+            input.key_down(key);
+        } else {
+            self.held.remove(&held);
+            if !self.held.iter().any(|(_, other)| self.keymap.lookup(other) == Some(key)) {
+                input.key_up(key);
+            }
+        }
+        true
+    }
+
+    pub fn button(&mut self, input: &mut Input, pad: usize, button: usize, down: bool) {
+        self.press_on(input, Device::Pad(pad), &format!("button{}", button), down);
+    }
+
+    // `directions` holds the hat's directions that are pushed, e.g. "up".
+    pub fn hat(&mut self, input: &mut Input, pad: usize, directions: &[&str]) {
+        for direction in HAT_DIRECTIONS {
+            self.press_on(input, Device::Pad(pad), &format!("hat_{}", direction), directions.contains(direction));
+        }
+    }
+
+    // A half of the axis presses once the stick is pushed past the threshold
+    // and lets go when it comes back below three quarters of it, so a stick
+    // resting near the threshold does not chatter.
+    pub fn axis(&mut self, input: &mut Input, pad: usize, axis: usize, value: i16) {
+        let threshold = self.keymap.threshold as i32;
+        for (name, push) in [(format!("axis{}-", axis), -(value as i32)), (format!("axis{}+", axis), value as i32)] {
+            let held = self.held.contains(&(Device::Pad(pad), name.clone()));
+            let down = push >= threshold || (held && push * 4 >= threshold * 3);
+            self.press_on(input, Device::Pad(pad), &name, down);
+        }
+    }
+
+    // Lets go of everything a device holds, for a pad that was unplugged.
+    pub fn release(&mut self, input: &mut Input, device: Device) {
+        let hosts: Vec<String> = self.held.iter().filter(|(holder, _)| *holder == device).map(|(_, host)| host.clone()).collect();
+        for host in hosts {
+            self.press_on(input, device, &host, false);
+        }
+    }
+}
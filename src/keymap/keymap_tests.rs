@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod keymap_tests {
+    use crate::keymap::*;
+    use crate::input::Input;
+
+    #[test]
+    fn test_builtin_keymap() {
+        let keymap = KeymapConfig::default().keymap_for("pong", 0);
+        assert_eq!(Some(0x1), keymap.lookup("1"));
+        assert_eq!(Some(0xC), keymap.lookup("4"));
+        assert_eq!(Some(0x0), keymap.lookup("x"));
+        assert_eq!(Some(0xF), keymap.lookup("v"));
+        assert_eq!(None, keymap.lookup("up"));
+        assert!(keymap == Keymap::default());
+    }
+
+    /**
+     * The defaults move the block by position for AZERTY. The Pong section
+     * then moves the paddle keys 1 and 4 to the arrows, which takes the up
+     * arrow away from 5 and A away from 4.
+    */
+    #[test]
+    fn test_layout_and_rom_overrides() {
+        let config = KeymapConfig::parse("
+            layout = azerty   # physical positions
+            5 = w, up
+
+            [rom pong]
+            1 = up, kp8
+            4 = down
+            6 = e
+        ").unwrap();
+        let other = config.keymap_for("games/tetris.ch8", 0x1234);
+        assert_eq!(Some(0x4), other.lookup("a"));
+        assert_eq!(Some(0x7), other.lookup("q"));
+        assert_eq!(Some(0x5), other.lookup("z"));
+        assert_eq!(Some(0x5), other.lookup("up"));
+        assert_eq!(Some(0x1), other.lookup("ampersand"));
+        assert_eq!(None, other.lookup("2"));
+
+        let pong = config.keymap_for("roms/PONG.ch8", 0x1234);
+        assert_eq!(Some(0x1), pong.lookup("up"));
+        assert_eq!(Some(0x1), pong.lookup("kp8"));
+        assert_eq!(Some(0x4), pong.lookup("down"));
+        assert_eq!(Some(0x5), pong.lookup("z"));
+        assert_eq!(None, pong.lookup("a"));
+        assert_eq!(Some(0x6), pong.lookup("e"));
+
+        let by_hash = KeymapConfig::parse("[rom 0000000000001234]\nmode = keysym\n").unwrap();
+        assert_eq!(Some(0x4), by_hash.keymap_for("x.ch8", 0x1234).lookup("q"));
+        assert_eq!(Some(0x4), KeymapConfig::parse("layout = dvorak").unwrap().keymap_for("x", 0).lookup("quote"));
+    }
+
+    #[test]
+    fn test_bad_keymaps() {
+        assert_eq!(Err(KeymapError { line: 2, message: String::from("unknown host key 'sapce'") }),
+            KeymapConfig::parse("1 = q\n2 = sapce"));
+        assert!(KeymapConfig::parse("10 = q").is_err());
+        assert!(KeymapConfig::parse("layout = colemak").is_err());
+        assert!(KeymapConfig::parse("[pong]").is_err());
+        assert!(KeymapConfig::parse("1 q").is_err());
+    }
+
+    #[test]
+    fn test_keypad_holds_shared_keys() {
+        let mut keymap = Keymap::default();
+        keymap.bind("up", 0x5);
+        let mut keypad = Keypad::new(keymap);
+        let mut input = Input::new();
+        assert!(keypad.press(&mut input, "w", true));
+        assert!(keypad.press(&mut input, "up", true));
+        assert!(keypad.press(&mut input, "w", false));
+        assert!(input.pressed(0x5));
+        assert!(keypad.press(&mut input, "up", false));
+        assert!(!input.pressed(0x5));
+        assert!(!keypad.press(&mut input, "space", true));
+    }
+
+    /**
+     * Pong moves the pad to the paddle keys 1 and 4 but keeps the keyboard
+     * keys 1 and Q on them. A pushed stick lets go only once it is back well
+     * below the threshold, and an unplugged pad lets go of everything.
+    */
+    #[test]
+    fn test_gamepad() {
+        let config = KeymapConfig::parse("
+            threshold = 50
+            [rom pong]
+            1 = hat_up, axis1-
+            4 = hat_down, axis1+
+        ").unwrap();
+        let other = config.keymap_for("tetris", 0);
+        assert_eq!(Some(0x2), other.lookup("hat_up"));
+        assert_eq!(Some(0x6), other.lookup("axis0+"));
+        assert_eq!(Some(0x5), other.lookup("button0"));
+
+        let pong = config.keymap_for("pong.ch8", 0);
+        assert_eq!(Some(0x1), pong.lookup("hat_up"));
+        assert_eq!(Some(0x1), pong.lookup("1"));
+        assert_eq!(Some(0x4), pong.lookup("axis1+"));
+        assert_eq!(Some(0x4), pong.lookup("q"));
+        assert_eq!(Some(0x8), pong.lookup("s"));
+        assert_eq!(Some(0x4), pong.lookup("hat_down"));
+
+        let mut keypad = Keypad::new(pong);
+        let mut input = Input::new();
+        keypad.axis(&mut input, 0, 1, i16::MIN);
+        assert!(input.pressed(0x1));
+        keypad.axis(&mut input, 0, 1, -14000);
+        assert!(input.pressed(0x1));
+        keypad.axis(&mut input, 0, 1, -12000);
+        assert!(!input.pressed(0x1));
+        keypad.axis(&mut input, 0, 1, 14000);
+        assert!(!input.pressed(0x4));
+
+        keypad.hat(&mut input, 0, &["down"]);
+        keypad.hat(&mut input, 1, &["down"]);
+        keypad.button(&mut input, 1, 0, true);
+        keypad.hat(&mut input, 0, &[]);
+        assert!(input.pressed(0x4));
+        keypad.release(&mut input, Device::Pad(1));
+        assert!(!input.pressed(0x4));
+        assert!(!input.pressed(0x5));
+
+        assert!(KeymapConfig::parse("5 = button32").is_err());
+        assert!(KeymapConfig::parse("5 = axis0").is_err());
+        assert!(KeymapConfig::parse("threshold = 100").is_err());
+    }
+}
@@ -0,0 +1,27 @@
+// The interpreter core. Nothing in here touches SDL, so it can be embedded in
+// other tools and tested on machines without a display; the SDL frontend lives
+// in the `emulator` binary behind the `sdl` feature.
+extern crate rand;
+
+pub mod chip8;
+pub mod cli;
+pub mod input;
+pub mod keymap;
+pub mod quirks;
+pub mod platform;
+pub mod random;
+pub mod instruction;
+pub mod display;
+pub mod audio;
+pub mod error;
+pub mod rom;
+pub mod savestate;
+pub mod rewind;
+pub mod debugger;
+pub mod condition;
+pub mod watch;
+pub mod history;
+pub mod disassembler;
+pub mod assembler;
+pub mod trace;
+pub mod tracediff;
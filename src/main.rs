@@ -1,36 +1,353 @@
-extern crate rand;
+extern crate emulator;
 extern crate sdl;
-use crate::display::Display;
-#[macro_use] extern crate prettytable;
 
-use sdl::event::Event;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
 
-mod chip8;
-mod input;
-mod display;
+use sdl::event::{Event, Key};
+
+use emulator::audio::{AudioSink, WavSink};
+use emulator::chip8;
+use emulator::chip8::Chip8;
+use emulator::cli::{self, Options};
+use emulator::debugger::{Debugger, StopReason};
+use emulator::error::{ExecError, OpcodePolicy};
+use emulator::keymap::{Keymap, KeymapConfig, Keypad};
+use emulator::rewind::Rewind;
+use emulator::rom;
+use emulator::savestate;
+use emulator::trace::Tracer;
+use crate::frontend::audio::SdlAudio;
+use crate::frontend::display::SdlDisplay;
+use crate::frontend::gamepad::Gamepads;
+use crate::frontend::keypad;
+use crate::frontend::prompt;
+
+mod frontend;
+
+const WAV_SAMPLE_RATE: u32 = 44100;
 
 
 fn main() {
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("emulator: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    let rom = match rom::read(&options.rom, options.platform.memory_size()) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("emulator: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if options.headless {
+        run_headless(&options, &rom);
+    } else {
+        run_window(&options, &rom);
+    }
+}
+
+// Save states sit next to the ROM, one file per slot.
+fn state_path(rom: &str, slot: u8) -> String {
+    format!("{}.state{}", rom, slot)
+}
+
+fn start_trace(chip: &mut Chip8, options: &Options) {
+    let path = match &options.trace {
+        Some(path) => path,
+        None => return
+    };
+    let file = match File::create(path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("emulator: could not create trace file {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+    let mut tracer = Tracer::new(Box::new(BufWriter::new(file)), options.trace_format);
+    if let Some((start, end)) = options.trace_range {
+        tracer.set_range(start, end);
+    }
+    if let Some(len) = options.trace_last {
+        tracer.set_ring(len as usize);
+    }
+    chip.set_tracer(tracer);
+}
+
+// Flushes the trace. Must run before exiting, which skips destructors.
+fn finish_trace(chip: &mut Chip8) {
+    if let Some(tracer) = chip.take_tracer() {
+        if let Err(e) = tracer.finish() {
+            eprintln!("emulator: could not write the trace: {}", e);
+        }
+    }
+}
+
+// The built-in keymap, with the --keymap file applied for this ROM.
+fn load_keymap(options: &Options, rom_hash: u64) -> Keymap {
+    let path = match &options.keymap {
+        Some(path) => path,
+        None => return Keymap::default()
+    };
+    let config = fs::read_to_string(path).map_err(|e| e.to_string())
+        .and_then(|text| KeymapConfig::parse(&text).map_err(|e| e.to_string()));
+    match config {
+        Ok(config) => config.keymap_for(&options.rom, rom_hash),
+        Err(e) => {
+            eprintln!("emulator: {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+// Sends the beep to the speaker, to the --record-audio file, or both.
+fn start_audio(chip: &mut Chip8, options: &Options, speaker: Option<&SdlAudio>) {
+    let mut sinks: Vec<Box<dyn AudioSink>> = Vec::new();
+    if let Some(speaker) = speaker {
+        sinks.push(Box::new(speaker.sink()));
+    }
+    if let Some(path) = &options.record_audio {
+        let recording = File::create(path).and_then(|file| WavSink::new(BufWriter::new(file), options.tone, WAV_SAMPLE_RATE));
+        match recording {
+            Ok(recording) => sinks.push(Box::new(recording)),
+            Err(e) => {
+                eprintln!("emulator: could not create audio recording {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+    if !sinks.is_empty() {
+        chip.set_audio_sink(Box::new(sinks));
+    }
+}
+
+// Completes the recording. Must run before exiting, like finish_trace.
+fn finish_audio(chip: &mut Chip8) {
+    if let Some(mut audio) = chip.take_audio_sink() {
+        if let Err(e) = audio.finish() {
+            eprintln!("emulator: could not write the audio recording: {}", e);
+        }
+    }
+}
+
+// Runs the machine to the end of its current frame, where the timers tick.
+// Returns why the debugger stopped the frame early, if it did; the next call
+// then finishes the frame.
+fn run_frame(chip: &mut Chip8, debugger: &mut Debugger) -> Result<Option<StopReason>, ExecError> {
+    for _ in 0..chip.cycles_left_in_frame() {
+        if let Some(stop) = debugger.run_cycle(chip)? {
+            return Ok(Some(stop));
+        }
+        if chip.has_exited() {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+fn run_headless(options: &Options, rom: &[u8]) {
     let mut chip = chip8::init_chip();
-    chip.load_rom(std::string::String::from("pong"));
-    // chip.load_rom(std::string::String::from("test_opcode.ch8"));
-    // chip.load_rom(std::string::String::from("Space Invaders [David Winter].ch8"));
+    chip.set_platform(options.platform);
+    chip.load_rom_bytes(rom).expect("ROM was validated when it was read");
+    chip.set_opcode_policy(options.policy);
+    chip.set_quirks(options.quirks);
+    chip.set_ips(options.ips);
+    if let Some(seed) = options.seed {
+        chip.set_seed(seed);
+    }
+    start_trace(&mut chip, options);
+    start_audio(&mut chip, options, None);
+
+    let mut debugger = Debugger::new();
+    let mut quit = options.debug && !prompt::run(&mut chip, &mut debugger);
+
+    let frames = options.frames.unwrap_or(0) as u64;
+    let mut result = Ok(None);
+    let mut frame = 0;
+    while !quit && frame < frames {
+        result = run_frame(&mut chip, &mut debugger);
+        match result {
+            Ok(Some(stop)) => {
+                println!("{}", stop);
+                quit = !prompt::run(&mut chip, &mut debugger);
+            },
+            Ok(None) if !chip.has_exited() => frame += 1,
+            // The fault left PC on the instruction, ready to look at.
+            Err(e) if options.policy == OpcodePolicy::Trap => {
+                println!("{}", e);
+                quit = !prompt::run(&mut chip, &mut debugger);
+            },
+            _ => break
+        }
+    }
+    finish_trace(&mut chip);
+    finish_audio(&mut chip);
+    if quit {
+        return;
+    }
 
-    sdl::init(&[sdl::InitFlag::Video, sdl::InitFlag::Audio, sdl::InitFlag::Timer]);
+    let width = chip.display.get_width();
+    for line in chip.display.get_gfx().iter().take(chip.display.get_height()) {
+        let row: String = line[..width].iter().map(|&pixel| ['.', '#', '+', '@'][pixel as usize & 3]).collect();
+        println!("{}", row);
+    }
+
+    if let Err(e) = result {
+        eprintln!("emulator: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run_window(options: &Options, rom: &[u8]) {
+    sdl::init(&[sdl::InitFlag::Video, sdl::InitFlag::Audio, sdl::InitFlag::Timer, sdl::InitFlag::Joystick]);
+
+    let mut chip = chip8::init_chip_with_backend(Box::new(SdlDisplay::new(options.scale as isize)));
+    chip.set_platform(options.platform);
+    chip.load_rom_bytes(rom).expect("ROM was validated when it was read");
+    chip.set_opcode_policy(options.policy);
+    chip.set_quirks(options.quirks);
+    chip.set_ips(options.ips);
+    if let Some(seed) = options.seed {
+        chip.set_seed(seed);
+    }
+    start_trace(&mut chip, options);
+
+    let speaker = match SdlAudio::open(options.tone) {
+        Ok(speaker) => {
+            speaker.set_muted(options.muted);
+            Some(speaker)
+        },
+        Err(e) => {
+            eprintln!("emulator: {}, continuing without sound", e);
+            None
+        }
+    };
+    start_audio(&mut chip, options, speaker.as_ref());
+    let mut keypad = Keypad::new(load_keymap(options, chip.get_rom_hash()));
+    let mut gamepads = Gamepads::open();
+
+    let frame_time = Duration::from_secs(1) / 60;
+    let mut paused = options.paused;
+    let mut frame = 0u64;
+    let mut slot = 1u8;
+    let mut rewind = Rewind::new(options.rewind_seconds as usize * 60, (options.rewind_budget as usize) << 20);
+    let mut rewinding = false;
+    let mut debugger = Debugger::new();
+    let mut debugging = options.debug;
 
     'main : loop {
+        let start = Instant::now();
+
         'event : loop {
-            match sdl::event::poll_event() {
-                Event::Quit                  => break 'main,
-                Event::None                  => break 'event,
-                Event::Key(key, state, _, _) => chip.input.press(key, state),
-                _                            => {}
+            let event = sdl::event::poll_event();
+            // Keys the keymap binds go to the keypad, even over a hotkey.
+            if let Event::Key(key, state, _, _) = event {
+                if keypad::key_name(key).is_some_and(|name| keypad.press(&mut chip.input, name, state)) {
+                    continue;
+                }
+            }
+            match event {
+                Event::Quit                     => break 'main,
+                Event::None                     => break 'event,
+                Event::Key(Key::P, true, _, _)  => paused = !paused,
+                Event::Key(Key::M, true, _, _)  => {
+                    if let Some(speaker) = speaker.as_ref() {
+                        let muted = speaker.toggle_mute();
+                        eprintln!("emulator: sound {}", if muted { "off" } else { "on" });
+                    }
+                },
+                Event::Key(Key::Backspace, state, _, _) => rewinding = state,
+                Event::Key(Key::F12, true, _, _) => debugging = true,
+                Event::Key(Key::F7, true, _, _) => gamepads.rescan(),
+                Event::Key(Key::F1, true, _, _) => slot = 1,
+                Event::Key(Key::F2, true, _, _) => slot = 2,
+                Event::Key(Key::F3, true, _, _) => slot = 3,
+                Event::Key(Key::F4, true, _, _) => slot = 4,
+                Event::Key(Key::F5, true, _, _) => {
+                    let path = state_path(&options.rom, slot);
+                    match savestate::save_to_file(&chip, &path) {
+                        Ok(()) => eprintln!("emulator: saved state to {}", path),
+                        Err(e) => eprintln!("emulator: {}", e)
+                    }
+                },
+                Event::Key(Key::F9, true, _, _) => {
+                    let path = state_path(&options.rom, slot);
+                    match savestate::load_from_file(&mut chip, &path) {
+                        Ok(()) => {
+                            rewind.clear();
+                            debugger.clear_history();
+                            eprintln!("emulator: loaded state from {}", path);
+                        },
+                        Err(e) => eprintln!("emulator: {}", e)
+                    }
+                },
+                _                               => {}
+            }
+        }
+
+        gamepads.poll(&mut keypad, &mut chip.input);
+
+        if debugging {
+            chip.display.draw_screen();
+            if !prompt::run(&mut chip, &mut debugger) {
+                break 'main;
             }
+            debugging = false;
         }
 
-        chip.cycle();
+        // Rewinding plays one recorded frame backwards per frame shown, so it
+        // runs at the same speed as the game did.
+        if rewinding {
+            rewind.step_back(&mut chip);
+        } else if !paused {
+            match run_frame(&mut chip, &mut debugger) {
+                Ok(Some(stop)) => {
+                    println!("{}", stop);
+                    debugging = true;
+                },
+                Ok(None) => {},
+                Err(e) if options.policy == OpcodePolicy::Trap => {
+                    println!("{}", e);
+                    debugging = true;
+                },
+                Err(e) => {
+                    eprintln!("emulator: {}", e);
+                    paused = true;
+                }
+            }
+            frame += 1;
+            rewind.record(&chip);
+        }
         chip.display.draw_screen();
+        // The machine only updates the speaker when a frame runs.
+        if let Some(speaker) = speaker.as_ref() {
+            if paused || debugging || rewinding {
+                speaker.stop();
+            }
+        }
+
+        if chip.has_exited() || options.frames.map_or(false, |frames| frame >= frames as u64) {
+            break 'main;
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            thread::sleep(frame_time - elapsed);
+        }
     }
 
+    finish_trace(&mut chip);
+    finish_audio(&mut chip);
+    drop(speaker);
     sdl::quit();
-} 
\ No newline at end of file
+}
@@ -0,0 +1,43 @@
+use crate::quirks::Quirks;
+
+// The instruction set a ROM was written for. Each platform extends the one
+// before it, so the ordering is meaningful: a SUPER-CHIP machine also runs
+// everything a plain CHIP-8 machine does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Platform {
+    #[default]
+    Chip8,
+    // SUPER-CHIP 1.1: 128x64 mode, scrolling, 16x16 sprites, big font, RPL flags.
+    SuperChip,
+    // XO-CHIP: 64 KiB of memory, two bitplanes, programmable audio.
+    XoChip
+}
+
+impl Platform {
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name.to_lowercase().as_str() {
+            "chip8" | "chip-8" => Some(Platform::Chip8),
+            "schip" | "superchip" | "super-chip" => Some(Platform::SuperChip),
+            "xochip" | "xo-chip" | "octo" => Some(Platform::XoChip),
+            _ => None
+        }
+    }
+
+    // The quirks ROMs for this platform usually expect.
+    pub fn default_quirks(self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks::modern(),
+            Platform::SuperChip => Quirks::schip(),
+            Platform::XoChip => Quirks::xo_chip()
+        }
+    }
+
+    // Bytes of addressable memory, including the interpreter area below 0x200.
+    pub fn memory_size(self) -> usize {
+        match self {
+            Platform::Chip8 | Platform::SuperChip => 0x1000,
+            Platform::XoChip => 0x10000
+        }
+    }
+}
+
@@ -0,0 +1,106 @@
+// The original CHIP-8 documentation left several instructions ambiguous and
+// later interpreters resolved them differently. ROMs tend to depend on the
+// behaviour of the interpreter they were written for, so each of those
+// choices can be switched here.
+
+// What FX55 and FX65 do to I after copying V0..VX.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryIncrement {
+    // I is left alone (SUPER-CHIP and most modern interpreters).
+    None,
+    // I ends up at I + X (the CHIP-48 off-by-one).
+    X,
+    // I ends up at I + X + 1, pointing after the last byte (COSMAC VIP).
+    XPlusOne
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY into VX instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    pub memory_increment: MemoryIncrement,
+    // BNNN jumps to XNN + VX instead of NNN + V0.
+    pub jump_uses_vx: bool,
+    // 8XY1/8XY2/8XY3 clear VF.
+    pub vf_reset: bool,
+    // Sprites are cut off at the screen edge instead of wrapping around.
+    pub clip_sprites: bool,
+    // DXYN waits for the next 60 Hz tick, so at most one sprite is drawn per frame.
+    pub display_wait: bool
+}
+
+impl Quirks {
+    pub fn vip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            memory_increment: MemoryIncrement::XPlusOne,
+            jump_uses_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+            display_wait: true
+        }
+    }
+
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            memory_increment: MemoryIncrement::X,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false
+        }
+    }
+
+    pub fn schip() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            memory_increment: MemoryIncrement::None,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false
+        }
+    }
+
+    pub fn xo_chip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            memory_increment: MemoryIncrement::XPlusOne,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false
+        }
+    }
+
+    // What this interpreter has always done, and what most ROMs written for
+    // emulators in the last decades expect.
+    pub fn modern() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            memory_increment: MemoryIncrement::None,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name.to_lowercase().as_str() {
+            "vip" | "cosmac" => Some(Quirks::vip()),
+            "chip48" | "chip-48" => Some(Quirks::chip48()),
+            "schip" | "superchip" => Some(Quirks::schip()),
+            "xochip" | "xo-chip" => Some(Quirks::xo_chip()),
+            "modern" => Some(Quirks::modern()),
+            _ => None
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::modern()
+    }
+}
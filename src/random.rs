@@ -0,0 +1,67 @@
+mod random_tests;
+
+// CXNN's random numbers: a xorshift generator seeded from the machine's seed.
+
+// Everything the generator holds, so putting it back is a copy: the seed it
+// started from and the xorshift words.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomState {
+    pub seed: u64,
+    pub words: [u32; 4]
+}
+
+pub struct Random {
+    state: RandomState
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        // SplitMix64 spreads the seed over the four words, which must not all
+        // be zero.
+        let mut mix = seed;
+        let mut words = [0; 4];
+        for pair in words.chunks_mut(2) {
+            mix = mix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = mix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            pair[0] = z as u32;
+            pair[1] = (z >> 32) as u32;
+        }
+        if words == [0; 4] {
+            words[0] = 1;
+        }
+        Random { state: RandomState { seed, words } }
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.state.seed
+    }
+
+    // Starts the sequence over from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Random::new(seed);
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        self.next_u32() as u8
+    }
+
+    // Marsaglia's xorshift128.
+    fn next_u32(&mut self) -> u32 {
+        let [x, y, z, w] = self.state.words;
+        let t = x ^ (x << 11);
+        let next = w ^ (w >> 19) ^ t ^ (t >> 8);
+        self.state.words = [y, z, w, next];
+        next
+    }
+
+    pub fn snapshot(&self) -> RandomState {
+        self.state
+    }
+
+    pub fn restore(&mut self, state: &RandomState) {
+        self.state = *state;
+    }
+}
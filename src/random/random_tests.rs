@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod random_tests {
+    use crate::random::*;
+
+    fn draw(random: &mut Random, count: usize) -> Vec<u8> {
+        (0..count).map(|_| random.next_byte()).collect()
+    }
+
+    #[test]
+    fn test_seed_decides_sequence() {
+        let (mut a, mut b) = (Random::new(42), Random::new(42));
+        let sequence = draw(&mut a, 16);
+        assert_eq!(sequence, draw(&mut b, 16));
+        assert!(sequence != draw(&mut Random::new(43), 16));
+
+        a.reseed(42);
+        assert_eq!(sequence, draw(&mut a, 16));
+    }
+
+    /**
+     * Restoring works both ways: forward from an earlier point in the same
+     * sequence, and into a generator that was seeded differently.
+     */
+    #[test]
+    fn test_restore_continues_sequence() {
+        let mut random = Random::new(7);
+        draw(&mut random, 10);
+        let state = random.snapshot();
+        let rest = draw(&mut random, 10);
+
+        random.restore(&state);
+        assert_eq!(rest, draw(&mut random, 10));
+        let mut other = Random::new(99);
+        draw(&mut other, 30);
+        other.restore(&state);
+        assert_eq!(rest, draw(&mut other, 10));
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+mod rewind_tests;
+
+use crate::chip8::Chip8;
+use crate::savestate::MachineState;
+
+// Keeps the last few minutes of machine states so the frontend can play a game
+// backwards. Only the newest state is stored in full; every older one is kept
+// as the XOR of itself and the state after it, run-length encoded. Consecutive
+// frames differ in a handful of bytes, so most entries are a few dozen bytes.
+pub struct Rewind {
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    depth: usize,
+    budget: usize,
+    used: usize
+}
+
+impl Rewind {
+    // Keeps at most `depth` states, and drops the oldest ones early if the
+    // deltas would take more than `budget` bytes.
+    pub fn new(depth: usize, budget: usize) -> Rewind {
+        Rewind {
+            latest: None,
+            deltas: VecDeque::new(),
+            depth,
+            budget,
+            used: 0
+        }
+    }
+
+    pub fn record(&mut self, chip: &Chip8) {
+        if self.depth == 0 {
+            return;
+        }
+        let state = chip.snapshot().encode(chip.get_rom_hash());
+        if let Some(latest) = self.latest.take() {
+            if latest.len() == state.len() {
+                let delta = compress(&latest, &state);
+                self.used += delta.len();
+                self.deltas.push_back(delta);
+            } else {
+                // The platform changed size under us; older states no longer fit.
+                self.deltas.clear();
+                self.used = 0;
+            }
+        }
+        self.latest = Some(state);
+
+        while self.deltas.len() + 1 > self.depth || (self.used > self.budget && !self.deltas.is_empty()) {
+            let oldest = self.deltas.pop_front().unwrap();
+            self.used -= oldest.len();
+        }
+    }
+
+    // Puts the machine back one recorded state and forgets the newer one.
+    // Returns false once there is nothing older left.
+    pub fn step_back(&mut self, chip: &mut Chip8) -> bool {
+        let delta = match self.deltas.pop_back() {
+            Some(delta) => delta,
+            None => return false
+        };
+        self.used -= delta.len();
+        let latest = self.latest.as_mut().expect("deltas are only kept with a newest state");
+        apply(latest, &delta);
+        let state = MachineState::decode(latest, chip.get_rom_hash()).expect("rewind states are written by record");
+        chip.restore(&state);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.used = 0;
+    }
+
+    // The number of states that step_back can still go back to.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    // Bytes held by the deltas, which is what the budget limits.
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+}
+
+// Encodes `a ^ b` as alternating runs: a varint count of equal bytes, a
+// varint count of differing bytes, then those bytes XORed. Applying the result
+// to either input gives the other.
+fn compress(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < a.len() {
+        let start = i;
+        while i < a.len() && a[i] == b[i] { i += 1 }
+        let same = i - start;
+        let start = i;
+        while i < a.len() && a[i] != b[i] { i += 1 }
+        if i == start { break }
+        write_varint(&mut out, same);
+        write_varint(&mut out, i - start);
+        out.extend(a[start..i].iter().zip(&b[start..i]).map(|(x, y)| x ^ y));
+    }
+    out
+}
+
+fn apply(state: &mut [u8], delta: &[u8]) {
+    let mut pos = 0;
+    let mut i = 0;
+    while i < delta.len() {
+        pos += read_varint(delta, &mut i);
+        let changed = read_varint(delta, &mut i);
+        for byte in state[pos..pos + changed].iter_mut() {
+            *byte ^= delta[i];
+            i += 1;
+        }
+        pos += changed;
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], i: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*i];
+        *i += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod rewind_tests {
+    use crate::rewind::*;
+    use crate::chip8::{init_chip, Chip8};
+
+    // Counts V0 up and draws the matching digit in a new spot every frame.
+    const COUNTER: [u8; 10] = [
+        0x70, 0x01, // 200: V0 += 1
+        0xF0, 0x29, // 202: I = font(V0)
+        0x71, 0x03, // 204: V1 += 3
+        0xD1, 0x15, // 206: draw at V1, V1
+        0x12, 0x00, // 208: jump 200
+    ];
+
+    fn set_up() -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(&COUNTER).unwrap();
+        chip
+    }
+
+    fn frame(chip: &mut Chip8) {
+        for _ in 0..5 {
+            chip.cycle().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_step_back_restores_earlier_frames() {
+        let mut chip8 = set_up();
+        let mut rewind = Rewind::new(100, 1 << 20);
+        let mut history = Vec::new();
+        for _ in 0..20 {
+            frame(&mut chip8);
+            rewind.record(&chip8);
+            history.push(chip8.snapshot());
+        }
+        assert_eq!(19, rewind.len());
+
+        for expected in history.iter().rev().skip(1) {
+            assert!(rewind.step_back(&mut chip8));
+            assert!(*expected == chip8.snapshot());
+        }
+        assert!(rewind.is_empty());
+        assert!(!rewind.step_back(&mut chip8));
+        assert_eq!(1, chip8.get_v0());
+    }
+
+    /**
+     * Playing on after a rewind records from the restored state, so a second
+     * rewind goes back through the new timeline.
+    */
+    #[test]
+    fn test_record_after_rewind() {
+        let mut chip8 = set_up();
+        let mut rewind = Rewind::new(100, 1 << 20);
+        for _ in 0..10 {
+            frame(&mut chip8);
+            rewind.record(&chip8);
+        }
+        for _ in 0..5 {
+            rewind.step_back(&mut chip8);
+        }
+        assert_eq!(5, chip8.get_v0());
+
+        chip8.execute(0x6A42).unwrap();
+        rewind.record(&chip8);
+        frame(&mut chip8);
+        rewind.record(&chip8);
+        rewind.step_back(&mut chip8);
+        assert_eq!(0x42, chip8.get_register(0xA));
+        rewind.step_back(&mut chip8);
+        assert_eq!(0, chip8.get_register(0xA));
+        assert_eq!(5, chip8.get_v0());
+    }
+
+    #[test]
+    fn test_depth_and_budget() {
+        let mut chip8 = set_up();
+        let mut rewind = Rewind::new(8, 1 << 20);
+        for _ in 0..20 {
+            frame(&mut chip8);
+            rewind.record(&chip8);
+        }
+        assert_eq!(7, rewind.len());
+
+        // A frame only changes a few registers and one sprite, so each delta
+        // is far smaller than the 12 KiB a full state takes.
+        let per_state = rewind.memory_used() / rewind.len();
+        assert!(per_state < 100, "{} bytes per state", per_state);
+
+        let mut rewind = Rewind::new(1000, per_state * 4);
+        for _ in 0..20 {
+            frame(&mut chip8);
+            rewind.record(&chip8);
+        }
+        assert!(rewind.len() <= 4);
+        assert!(rewind.memory_used() <= per_state * 4);
+
+        let mut disabled = Rewind::new(0, 1 << 20);
+        disabled.record(&chip8);
+        assert!(!disabled.step_back(&mut chip8));
+    }
+}
@@ -0,0 +1,61 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Programs are loaded at 0x200; everything below that belonged to the
+// original interpreter and now holds the font.
+pub const ROM_START: usize = 0x200;
+
+#[derive(Debug)]
+pub enum RomError {
+    NotFound(PathBuf),
+    Io(PathBuf, io::Error),
+    Empty,
+    TooLarge { size: usize, max: usize }
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomError::NotFound(path) => write!(f, "ROM file {} does not exist", path.display()),
+            RomError::Io(path, e) => write!(f, "could not read ROM file {}: {}", path.display(), e),
+            RomError::Empty => write!(f, "ROM is empty"),
+            RomError::TooLarge { size, max } => write!(f, "ROM is {} bytes, but at most {} bytes fit in memory", size, max)
+        }
+    }
+}
+
+impl Error for RomError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RomError::Io(_, e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+// Reads a ROM from disk and checks that it would fit in `memory_size` bytes,
+// without touching a machine. Frontends use this to report a bad file before
+// opening a window.
+pub fn read<P: AsRef<Path>>(path: P, memory_size: usize) -> Result<Vec<u8>, RomError> {
+    let path = path.as_ref();
+    let rom = fs::read(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => RomError::NotFound(path.to_path_buf()),
+        _ => RomError::Io(path.to_path_buf(), e)
+    })?;
+    validate(&rom, memory_size)?;
+    Ok(rom)
+}
+
+pub fn validate(rom: &[u8], memory_size: usize) -> Result<(), RomError> {
+    let max = memory_size - ROM_START;
+    if rom.is_empty() {
+        return Err(RomError::Empty);
+    }
+    if rom.len() > max {
+        return Err(RomError::TooLarge { size: rom.len(), max });
+    }
+    Ok(())
+}
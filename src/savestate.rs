@@ -0,0 +1,263 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::chip8::Chip8;
+use crate::display::{Framebuffer, MAX_HEIGHT, MAX_WIDTH};
+use crate::platform::Platform;
+use crate::random::RandomState;
+
+// A save state file is a fixed header followed by the machine state, all
+// integers little-endian:
+//
+//   "C8ST"  magic
+//   u16     format version
+//   u64     FNV-1a hash of the ROM the state was saved with
+//   ...     the fields of MachineState, in declaration order
+//
+// Bump VERSION whenever the layout changes; older files are then rejected
+// instead of being misread.
+pub const MAGIC: &[u8; 4] = b"C8ST";
+pub const VERSION: u16 = 7;
+
+#[derive(Debug)]
+pub enum SaveStateError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    RomMismatch { expected: u64, found: u64 },
+    Truncated,
+    Invalid(&'static str)
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveStateError::Io(e) => write!(f, "could not access save state: {}", e),
+            SaveStateError::BadMagic => write!(f, "not a save state file"),
+            SaveStateError::UnsupportedVersion(version) => write!(f, "save state format version {} is not supported (expected {})", version, VERSION),
+            SaveStateError::RomMismatch { expected, found } => write!(f, "save state is for ROM {:016X}, but ROM {:016X} is loaded", found, expected),
+            SaveStateError::Truncated => write!(f, "save state is truncated"),
+            SaveStateError::Invalid(what) => write!(f, "save state is corrupt: bad {}", what)
+        }
+    }
+}
+
+impl Error for SaveStateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveStateError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for SaveStateError {
+    fn from(e: io::Error) -> SaveStateError {
+        SaveStateError::Io(e)
+    }
+}
+
+// Everything needed to put a machine back exactly where it was. Configuration
+// such as quirks and the opcode policy is not part of it; that belongs to the
+// frontend, not the running program.
+#[derive(Clone, PartialEq)]
+pub struct MachineState {
+    pub platform: Platform,
+    pub program_counter: u16,
+    pub i_register: u16,
+    pub registers: [u8; 16],
+    pub sp: u8,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub stack: [u16; 16],
+    pub memory: Vec<u8>,
+    pub rpl: [u8; 16],
+    pub exited: bool,
+    pub audio_pattern: [u8; 16],
+    pub pitch: u8,
+    pub hires: bool,
+    pub planes: u8,
+    pub gfx: Framebuffer,
+    // The keypad when the state was taken. Chip8::restore does not apply it.
+    pub keys: u16,
+    // The key an FX0A is waiting to see released, if any.
+    pub key_wait: Option<u8>,
+    pub cycles: u64,
+    pub frames: u64,
+    pub frame_cycles: u64,
+    pub random: RandomState
+}
+
+impl MachineState {
+    pub fn encode(&self, rom_hash: u64) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.memory.len() + MAX_WIDTH * MAX_HEIGHT + 128);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&rom_hash.to_le_bytes());
+
+        out.push(self.platform as u8);
+        out.extend_from_slice(&self.program_counter.to_le_bytes());
+        out.extend_from_slice(&self.i_register.to_le_bytes());
+        out.extend_from_slice(&self.registers);
+        out.push(self.sp);
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        for address in self.stack.iter() {
+            out.extend_from_slice(&address.to_le_bytes());
+        }
+        out.extend_from_slice(&(self.memory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&self.rpl);
+        out.push(self.exited as u8);
+        out.extend_from_slice(&self.audio_pattern);
+        out.push(self.pitch);
+        out.push(self.hires as u8);
+        out.push(self.planes);
+        for line in self.gfx.iter() {
+            out.extend_from_slice(line);
+        }
+        out.extend_from_slice(&self.keys.to_le_bytes());
+        out.push(self.key_wait.unwrap_or(0xFF));
+        out.extend_from_slice(&self.cycles.to_le_bytes());
+        out.extend_from_slice(&self.frames.to_le_bytes());
+        out.extend_from_slice(&self.frame_cycles.to_le_bytes());
+        out.extend_from_slice(&self.random.seed.to_le_bytes());
+        for word in self.random.words.iter() {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    // Checks the header against the loaded ROM before reading anything else,
+    // so a state from another game is never half applied.
+    pub fn decode(bytes: &[u8], rom_hash: u64) -> Result<MachineState, SaveStateError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+        let found = reader.u64()?;
+        if found != rom_hash {
+            return Err(SaveStateError::RomMismatch { expected: rom_hash, found });
+        }
+
+        let platform = match reader.u8()? {
+            0 => Platform::Chip8,
+            1 => Platform::SuperChip,
+            2 => Platform::XoChip,
+            _ => return Err(SaveStateError::Invalid("platform"))
+        };
+        let program_counter = reader.u16()?;
+        let i_register = reader.u16()?;
+        let registers = reader.array()?;
+        let sp = reader.u8()?;
+        if sp > 16 {
+            return Err(SaveStateError::Invalid("stack pointer"));
+        }
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let mut stack = [0; 16];
+        for address in stack.iter_mut() {
+            *address = reader.u16()?;
+        }
+        let memory_size = reader.u32()? as usize;
+        if memory_size != platform.memory_size() {
+            return Err(SaveStateError::Invalid("memory size"));
+        }
+        let memory = reader.take(memory_size)?.to_vec();
+        let rpl = reader.array()?;
+        let exited = reader.u8()? != 0;
+        let audio_pattern = reader.array()?;
+        let pitch = reader.u8()?;
+        let hires = reader.u8()? != 0;
+        let planes = reader.u8()?;
+        let mut gfx = [[0; MAX_WIDTH]; MAX_HEIGHT];
+        for line in gfx.iter_mut() {
+            line.copy_from_slice(reader.take(MAX_WIDTH)?);
+        }
+        let keys = reader.u16()?;
+        let key_wait = match reader.u8()? {
+            0xFF => None,
+            key if key < 16 => Some(key),
+            _ => return Err(SaveStateError::Invalid("waiting key"))
+        };
+        let cycles = reader.u64()?;
+        let frames = reader.u64()?;
+        let frame_cycles = reader.u64()?;
+        let seed = reader.u64()?;
+        let mut words = [0; 4];
+        for word in words.iter_mut() {
+            *word = reader.u32()?;
+        }
+        let random = RandomState { seed, words };
+
+        Ok(MachineState {
+            platform, program_counter, i_register, registers, sp, delay_timer, sound_timer, stack,
+            memory, rpl, exited, audio_pattern, pitch, hires, planes, gfx, keys, key_wait, cycles, frames, frame_cycles, random
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.bytes.len() < len {
+            return Err(SaveStateError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array(&mut self) -> Result<[u8; 16], SaveStateError> {
+        let mut array = [0; 16];
+        array.copy_from_slice(self.take(16)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveStateError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, SaveStateError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+// 64-bit FNV-1a. Not cryptographic, just enough to tell ROMs apart.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn save_to_file<P: AsRef<Path>>(chip: &Chip8, path: P) -> Result<(), SaveStateError> {
+    fs::write(path, chip.save_state())?;
+    Ok(())
+}
+
+pub fn load_from_file<P: AsRef<Path>>(chip: &mut Chip8, path: P) -> Result<(), SaveStateError> {
+    let bytes = fs::read(path)?;
+    chip.load_state(&bytes)
+}
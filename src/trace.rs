@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+mod trace_tests;
+
+use crate::instruction::decode;
+
+// The machine as it was just before one instruction ran.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceRecord {
+    // Instructions executed before this one.
+    pub cycle: u64,
+    pub pc: u16,
+    pub opcode: u16,
+    pub registers: [u8; 16],
+    pub i: u16,
+    pub sp: u8,
+    pub delay_timer: u8,
+    pub sound_timer: u8
+}
+
+// One line per record:
+//
+//   cycle PC opcode V=V0..VF I=... SP=.. DT=.. ST=..  ; mnemonic
+//
+// with everything but the cycle and SP in hexadecimal.
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let registers: String = self.registers.iter().map(|v| format!("{:02X}", v)).collect();
+        let mnemonic = decode(self.opcode).map_or_else(|_| String::from("???"), |instruction| instruction.to_string());
+        write!(f, "{:>8} {:03X} {:04X} V={} I={:03X} SP={} DT={:02X} ST={:02X}  ; {}",
+            self.cycle, self.pc, self.opcode, registers, self.i, self.sp, self.delay_timer, self.sound_timer, mnemonic)
+    }
+}
+
+// Binary traces start with "C8TR" and a little-endian u16 version, followed
+// by RECORD_SIZE bytes per record holding the fields in declaration order,
+// also little-endian.
+pub const MAGIC: &[u8; 4] = b"C8TR";
+pub const VERSION: u16 = 1;
+pub const RECORD_SIZE: usize = 33;
+
+impl TraceRecord {
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.cycle.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.pc.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.opcode.to_le_bytes());
+        bytes[12..28].copy_from_slice(&self.registers);
+        bytes[28..30].copy_from_slice(&self.i.to_le_bytes());
+        bytes[30] = self.sp;
+        bytes[31] = self.delay_timer;
+        bytes[32] = self.sound_timer;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> TraceRecord {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let mut cycle = [0; 8];
+        cycle.copy_from_slice(&bytes[0..8]);
+        let mut registers = [0; 16];
+        registers.copy_from_slice(&bytes[12..28]);
+        TraceRecord {
+            cycle: u64::from_le_bytes(cycle),
+            pc: u16_at(8),
+            opcode: u16_at(10),
+            registers,
+            i: u16_at(28),
+            sp: bytes[30],
+            delay_timer: bytes[31],
+            sound_timer: bytes[32]
+        }
+    }
+}
+
+impl FromStr for TraceRecord {
+    type Err = String;
+
+    // Reads a line written by Display. The mnemonic is ignored.
+    fn from_str(line: &str) -> Result<TraceRecord, String> {
+        let fields: Vec<&str> = line.split(';').next().unwrap_or("").split_whitespace().collect();
+        if fields.len() != 8 {
+            return Err(format!("expected 8 fields before the mnemonic, got {}", fields.len()));
+        }
+        let hex = |field: &str, prefix: &str| -> Result<u32, String> {
+            let digits = field.strip_prefix(prefix).ok_or_else(|| format!("expected {}, got '{}'", prefix, field))?;
+            u32::from_str_radix(digits, 16).map_err(|_| format!("bad number '{}'", field))
+        };
+        let values = fields[3].strip_prefix("V=").filter(|values| values.len() == 32 && values.is_ascii())
+            .ok_or_else(|| format!("expected V= and 16 registers, got '{}'", fields[3]))?;
+        let mut registers = [0; 16];
+        for (x, register) in registers.iter_mut().enumerate() {
+            *register = u8::from_str_radix(&values[x * 2..x * 2 + 2], 16).map_err(|_| format!("bad registers '{}'", values))?;
+        }
+        Ok(TraceRecord {
+            cycle: fields[0].parse().map_err(|_| format!("bad cycle '{}'", fields[0]))?,
+            pc: hex(fields[1], "")? as u16,
+            opcode: hex(fields[2], "")? as u16,
+            registers,
+            i: hex(fields[4], "I=")? as u16,
+            sp: fields[5].strip_prefix("SP=").and_then(|sp| sp.parse().ok()).ok_or_else(|| format!("bad SP '{}'", fields[5]))?,
+            delay_timer: hex(fields[6], "DT=")? as u8,
+            sound_timer: hex(fields[7], "ST=")? as u8
+        })
+    }
+}
+
+// Reads back a trace in either format.
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vec<TraceRecord>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.starts_with(MAGIC) {
+        return read_binary(&bytes[..]);
+    }
+    let text = String::from_utf8(bytes).map_err(|_| invalid(String::from("not a trace")))?;
+    text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| line.parse().map_err(|e| invalid(format!("line {}: {}", i + 1, e))))
+        .collect()
+}
+
+// Reads back a binary trace.
+pub fn read_binary<R: Read>(mut reader: R) -> io::Result<Vec<TraceRecord>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() < 6 || &bytes[..4] != MAGIC {
+        return Err(invalid("not a binary trace"));
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(invalid(&format!("trace format version {} is not supported (expected {})", version, VERSION)));
+    }
+    let body = &bytes[6..];
+    if body.len() % RECORD_SIZE != 0 {
+        return Err(invalid("trace is truncated"));
+    }
+    Ok(body.chunks(RECORD_SIZE).map(|chunk| {
+        let mut record = [0; RECORD_SIZE];
+        record.copy_from_slice(chunk);
+        TraceRecord::from_bytes(&record)
+    }).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Text,
+    Binary
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<TraceFormat> {
+        match name.to_lowercase().as_str() {
+            "text" => Some(TraceFormat::Text),
+            "binary" => Some(TraceFormat::Binary),
+            _ => None
+        }
+    }
+}
+
+// Writes a record for every instruction the machine runs, once installed with
+// Chip8::set_tracer. Write errors stop the tracing; finish reports them.
+pub struct Tracer {
+    out: Box<dyn Write>,
+    format: TraceFormat,
+    range: Option<(u16, u16)>,
+    ring: Option<(usize, VecDeque<TraceRecord>)>,
+    started: bool,
+    error: Option<io::Error>
+}
+
+impl Tracer {
+    pub fn new(out: Box<dyn Write>, format: TraceFormat) -> Tracer {
+        Tracer { out, format, range: None, ring: None, started: false, error: None }
+    }
+
+    // Only records instructions at addresses from `start` to `end`, both
+    // included.
+    pub fn set_range(&mut self, start: u16, end: u16) {
+        self.range = Some((start, end));
+    }
+
+    // Keeps just the last `len` records in memory instead of writing them,
+    // and writes those when the machine faults.
+    pub fn set_ring(&mut self, len: usize) {
+        self.ring = Some((len, VecDeque::with_capacity(len)));
+    }
+
+    pub fn record(&mut self, record: TraceRecord) {
+        if let Some((start, end)) = self.range {
+            if record.pc < start || record.pc > end {
+                return;
+            }
+        }
+        match self.ring.as_mut() {
+            Some((len, records)) => {
+                if records.len() == *len {
+                    records.pop_front();
+                }
+                if *len > 0 {
+                    records.push_back(record);
+                }
+            },
+            None => self.write(&record)
+        }
+    }
+
+    // Writes out and empties the ring buffer. Without one there is nothing
+    // held back to write.
+    pub fn dump(&mut self) {
+        let records = match self.ring.as_mut() {
+            Some((_, records)) => records.drain(..).collect::<Vec<_>>(),
+            None => return
+        };
+        for record in &records {
+            self.write(record);
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()
+    }
+
+    fn write(&mut self, record: &TraceRecord) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = match self.format {
+            TraceFormat::Text => writeln!(self.out, "{}", record),
+            TraceFormat::Binary => {
+                let header = if self.started { Ok(()) } else {
+                    self.out.write_all(MAGIC).and_then(|()| self.out.write_all(&VERSION.to_le_bytes()))
+                };
+                header.and_then(|()| self.out.write_all(&record.to_bytes()))
+            }
+        };
+        self.started = true;
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+}
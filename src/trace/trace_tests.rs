@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod trace_tests {
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+    use crate::trace::*;
+    use crate::chip8::{init_chip, Chip8};
+
+    // A writer the test can still read after handing it to a Tracer.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const PROGRAM: [u8; 8] = [
+        0x60, 0x05, // 200: V0 = 5
+        0x70, 0x01, // 202: V0 += 1
+        0x12, 0x02, // 204: jump 202
+        0xFF, 0xFF, // 206: not an instruction
+    ];
+
+    fn set_up(tracer: Tracer) -> Chip8 {
+        let mut chip = init_chip();
+        chip.load_rom_bytes(&PROGRAM).unwrap();
+        chip.set_tracer(tracer);
+        chip
+    }
+
+    #[test]
+    fn test_text_trace() {
+        let out = Shared::default();
+        let mut chip8 = set_up(Tracer::new(Box::new(out.clone()), TraceFormat::Text));
+        for _ in 0..3 {
+            chip8.cycle().unwrap();
+        }
+        chip8.take_tracer().unwrap().finish().unwrap();
+
+        let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(3, lines.len());
+        assert_eq!("       0 200 6005 V=00000000000000000000000000000000 I=000 SP=0 DT=00 ST=00  ; LD V0, 0x05", lines[0]);
+        assert_eq!("       2 204 1202 V=06000000000000000000000000000000 I=000 SP=0 DT=00 ST=00  ; JP 0x202", lines[2]);
+    }
+
+    #[test]
+    fn test_binary_trace_with_range() {
+        let out = Shared::default();
+        let mut tracer = Tracer::new(Box::new(out.clone()), TraceFormat::Binary);
+        tracer.set_range(0x202, 0x202);
+        let mut chip8 = set_up(tracer);
+        for _ in 0..7 {
+            chip8.cycle().unwrap();
+        }
+        chip8.take_tracer().unwrap().finish().unwrap();
+
+        let records = read_binary(&out.0.borrow()[..]).unwrap();
+        assert_eq!(vec![1, 3, 5], records.iter().map(|record| record.cycle).collect::<Vec<_>>());
+        assert!(records.iter().all(|record| record.pc == 0x202 && record.opcode == 0x7001));
+        assert_eq!(6, records[1].registers[0]);
+    }
+
+    /**
+     * In ring mode nothing is written until the machine faults, and then only
+     * the last few instructions.
+     */
+    #[test]
+    fn test_ring_dumps_on_fault() {
+        let out = Shared::default();
+        let mut tracer = Tracer::new(Box::new(out.clone()), TraceFormat::Text);
+        tracer.set_ring(2);
+        let mut chip8 = set_up(tracer);
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        chip8.cycle().unwrap();
+        assert!(out.0.borrow().is_empty());
+
+        chip8.execute(0x1206).unwrap();
+        assert!(chip8.cycle().is_err());
+        let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+        let pcs: Vec<&str> = text.lines().map(|line| &line[9..12]).collect();
+        assert_eq!(vec!["204", "206"], pcs);
+    }
+}
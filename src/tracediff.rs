@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::fmt;
+mod tracediff_tests;
+
+use crate::chip8::Chip8;
+use crate::error::ExecError;
+use crate::trace::TraceRecord;
+
+// Finds the first point where two runs of a program stop agreeing: either two
+// machines run in lockstep, or one machine checked against a trace recorded
+// earlier. Traces only hold the CPU state, so against a trace memory and the
+// screen are not compared.
+
+// One way the runs disagree, with the value in run A first. Against a
+// reference trace, A is the trace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    Pc(u16, u16),
+    Opcode(u16, u16),
+    Register(usize, u8, u8),
+    I(u16, u16),
+    Sp(u8, u8),
+    Stack(usize, u16, u16),
+    DelayTimer(u8, u8),
+    SoundTimer(u8, u8),
+    Memory { address: usize, a: u8, b: u8 },
+    Resolution(bool, bool),
+    Pixel { x: usize, y: usize, a: u8, b: u8 },
+    // One run faulted or exited and the other did not, or not the same way.
+    Stopped(String, String)
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let resolution = |hires: bool| if hires { "hires" } else { "lores" };
+        match self {
+            Difference::Pc(a, b) => write!(f, "PC {:03X} / {:03X}", a, b),
+            Difference::Opcode(a, b) => write!(f, "opcode {:04X} / {:04X}", a, b),
+            Difference::Register(x, a, b) => write!(f, "V{:X} {:02X} / {:02X}", x, a, b),
+            Difference::I(a, b) => write!(f, "I {:03X} / {:03X}", a, b),
+            Difference::Sp(a, b) => write!(f, "SP {} / {}", a, b),
+            Difference::Stack(level, a, b) => write!(f, "stack[{}] {:03X} / {:03X}", level, a, b),
+            Difference::DelayTimer(a, b) => write!(f, "DT {:02X} / {:02X}", a, b),
+            Difference::SoundTimer(a, b) => write!(f, "ST {:02X} / {:02X}", a, b),
+            Difference::Memory { address, a, b } => write!(f, "memory[{:#05X}] {:02X} / {:02X}", address, a, b),
+            Difference::Resolution(a, b) => write!(f, "resolution {} / {}", resolution(*a), resolution(*b)),
+            Difference::Pixel { x, y, a, b } => write!(f, "pixel ({}, {}) {} / {}", x, y, a, b),
+            Difference::Stopped(a, b) => write!(f, "{} / {}", a, b)
+        }
+    }
+}
+
+pub struct Divergence {
+    // Instructions executed when the difference was seen.
+    pub cycle: u64,
+    pub differences: Vec<Difference>,
+    // The last instructions of both runs, oldest first. With two machines the
+    // last pair is the one that made them differ; against a trace it is the
+    // first pair that differs.
+    pub context: Vec<(TraceRecord, TraceRecord)>
+}
+
+// Long lists, typically a screen full of pixels, are cut short.
+const MAX_SHOWN: usize = 16;
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "runs differ after {} instructions (A / B):", self.cycle)?;
+        for difference in self.differences.iter().take(MAX_SHOWN) {
+            writeln!(f, "  {}", difference)?;
+        }
+        if self.differences.len() > MAX_SHOWN {
+            writeln!(f, "  ... and {} more", self.differences.len() - MAX_SHOWN)?;
+        }
+        if !self.context.is_empty() {
+            writeln!(f, "leading up to it:")?;
+        }
+        for (a, b) in &self.context {
+            if a == b {
+                writeln!(f, "    {}", a)?;
+            } else {
+                writeln!(f, "  A {}", a)?;
+                writeln!(f, "  B {}", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Everything a trace record holds except the cycle count.
+pub fn compare_records(a: &TraceRecord, b: &TraceRecord) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    if a.pc != b.pc {
+        differences.push(Difference::Pc(a.pc, b.pc));
+    }
+    if a.opcode != b.opcode {
+        differences.push(Difference::Opcode(a.opcode, b.opcode));
+    }
+    for x in 0..16 {
+        if a.registers[x] != b.registers[x] {
+            differences.push(Difference::Register(x, a.registers[x], b.registers[x]));
+        }
+    }
+    if a.i != b.i {
+        differences.push(Difference::I(a.i, b.i));
+    }
+    if a.sp != b.sp {
+        differences.push(Difference::Sp(a.sp, b.sp));
+    }
+    if a.delay_timer != b.delay_timer {
+        differences.push(Difference::DelayTimer(a.delay_timer, b.delay_timer));
+    }
+    if a.sound_timer != b.sound_timer {
+        differences.push(Difference::SoundTimer(a.sound_timer, b.sound_timer));
+    }
+    differences
+}
+
+static ZEROS: [u8; 0x10000] = [0; 0x10000];
+
+// The CPU state, the live part of the stack, memory and the screen.
+pub fn compare_machines(a: &Chip8, b: &Chip8) -> Vec<Difference> {
+    let mut differences = compare_records(&a.get_trace_record(), &b.get_trace_record());
+    let depth = (*a.get_sp()).max(*b.get_sp()) as usize;
+    for level in 0..depth.min(16) {
+        let (return_a, return_b) = (a.get_stack()[level], b.get_stack()[level]);
+        if return_a != return_b {
+            differences.push(Difference::Stack(level, return_a as u16, return_b as u16));
+        }
+    }
+    // This runs after every instruction, so memory and the screen are only
+    // gone through byte by byte once they are known to differ.
+    let (memory_a, memory_b) = (a.get_memory(), b.get_memory());
+    let common = memory_a.len().min(memory_b.len());
+    if memory_a[..common] != memory_b[..common] {
+        for address in 0..common {
+            if memory_a[address] != memory_b[address] {
+                differences.push(Difference::Memory { address, a: memory_a[address], b: memory_b[address] });
+            }
+        }
+    }
+    // When one machine has more memory, its extra bytes count as differing
+    // from zero, and are only looked at once one of them is set.
+    let extra = if memory_a.len() > common { &memory_a[common..] } else { &memory_b[common..] };
+    if extra != &ZEROS[..extra.len()] {
+        for (offset, &byte) in extra.iter().enumerate().filter(|&(_, &byte)| byte != 0) {
+            let address = common + offset;
+            let (a, b) = if memory_a.len() > common { (byte, 0) } else { (0, byte) };
+            differences.push(Difference::Memory { address, a, b });
+        }
+    }
+    if a.display.is_hires() != b.display.is_hires() {
+        differences.push(Difference::Resolution(a.display.is_hires(), b.display.is_hires()));
+    }
+    if a.display.get_gfx() != b.display.get_gfx() {
+        for (y, (line_a, line_b)) in a.display.get_gfx().iter().zip(b.display.get_gfx().iter()).enumerate() {
+            for (x, (&pixel_a, &pixel_b)) in line_a.iter().zip(line_b.iter()).enumerate() {
+                if pixel_a != pixel_b {
+                    differences.push(Difference::Pixel { x, y, a: pixel_a, b: pixel_b });
+                }
+            }
+        }
+    }
+    differences
+}
+
+// Runs both machines one instruction at a time for up to `cycles`
+// instructions and stops at the first one after which they differ. Both are
+// given the same keys: `inputs` holds (cycle, key mask) pairs in cycle order,
+// each held from its cycle on.
+pub fn diff_runs(a: &mut Chip8, b: &mut Chip8, inputs: &[(u64, u16)], cycles: u64, context: usize) -> Option<Divergence> {
+    let differences = compare_machines(a, b);
+    if !differences.is_empty() {
+        return Some(Divergence { cycle: a.get_cycles(), differences, context: Vec::new() });
+    }
+    let mut recent = VecDeque::new();
+    for _ in 0..cycles {
+        apply_inputs(a, inputs);
+        apply_inputs(b, inputs);
+        remember(&mut recent, context + 1, (a.get_trace_record(), b.get_trace_record()));
+        let result_a = a.cycle();
+        let result_b = b.cycle();
+
+        let (stopped_a, stopped_b) = (stopped(a, result_a), stopped(b, result_b));
+        let mut differences = Vec::new();
+        if stopped_a != stopped_b {
+            differences.push(Difference::Stopped(describe(&stopped_a), describe(&stopped_b)));
+        }
+        differences.extend(compare_machines(a, b));
+        if !differences.is_empty() {
+            return Some(Divergence { cycle: a.get_cycles(), differences, context: recent.into() });
+        }
+        if stopped_a.is_some() {
+            return None;
+        }
+    }
+    None
+}
+
+// Runs the machine along a trace recorded earlier and stops at the first
+// record it does not match. The trace may skip instructions, as one limited
+// to an address range does.
+pub fn diff_against_trace(chip: &mut Chip8, reference: &[TraceRecord], inputs: &[(u64, u16)], context: usize) -> Option<Divergence> {
+    let mut recent = VecDeque::new();
+    for expected in reference {
+        while chip.get_cycles() < expected.cycle {
+            apply_inputs(chip, inputs);
+            let result = chip.cycle();
+            if let Some(reason) = stopped(chip, result) {
+                let differences = vec![Difference::Stopped(describe(&None), reason)];
+                return Some(Divergence { cycle: chip.get_cycles(), differences, context: recent.into() });
+            }
+        }
+        if chip.get_cycles() > expected.cycle {
+            continue;
+        }
+        let actual = chip.get_trace_record();
+        remember(&mut recent, context + 1, (*expected, actual));
+        let differences = compare_records(expected, &actual);
+        if !differences.is_empty() {
+            return Some(Divergence { cycle: expected.cycle, differences, context: recent.into() });
+        }
+    }
+    None
+}
+
+fn remember(recent: &mut VecDeque<(TraceRecord, TraceRecord)>, len: usize, records: (TraceRecord, TraceRecord)) {
+    if len == 0 {
+        return;
+    }
+    if recent.len() == len {
+        recent.pop_front();
+    }
+    recent.push_back(records);
+}
+
+fn apply_inputs(chip: &mut Chip8, inputs: &[(u64, u16)]) {
+    let cycle = chip.get_cycles();
+    if let Some(&(_, keys)) = inputs.iter().rev().find(|&&(at, _)| at <= cycle) {
+        chip.input.set_mask(keys);
+    }
+}
+
+// Why the machine stopped after a cycle, or None if it is still running.
+fn stopped(chip: &Chip8, result: Result<(), ExecError>) -> Option<String> {
+    match result {
+        Err(e) => Some(e.to_string()),
+        Ok(()) if chip.has_exited() => Some(String::from("exited")),
+        Ok(()) => None
+    }
+}
+
+fn describe(stopped: &Option<String>) -> String {
+    stopped.clone().unwrap_or_else(|| String::from("running"))
+}
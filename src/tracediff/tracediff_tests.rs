@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tracediff_tests {
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+    use crate::tracediff::*;
+    use crate::chip8::{init_chip, Chip8};
+    use crate::platform::Platform;
+    use crate::quirks::Quirks;
+    use crate::trace::{self, TraceFormat, Tracer};
+
+    const PROGRAM: [u8; 10] = [
+        0x61, 0x03, // 200: V1 = 3
+        0x60, 0x10, // 202: V0 = 0x10
+        0x80, 0x16, // 204: V0 >>= 1, from V1 on the VIP
+        0x70, 0x01, // 206: V0 += 1
+        0x00, 0xFD, // 208: exit
+    ];
+
+    fn machine(quirks: Quirks) -> Chip8 {
+        let mut chip = init_chip();
+        chip.set_platform(Platform::SuperChip);
+        chip.load_rom_bytes(&PROGRAM).unwrap();
+        chip.set_quirks(quirks);
+        chip
+    }
+
+    #[test]
+    fn test_same_configuration_does_not_diverge() {
+        let (mut a, mut b) = (machine(Quirks::modern()), machine(Quirks::modern()));
+        assert!(diff_runs(&mut a, &mut b, &[], 100, 4).is_none());
+        assert!(a.has_exited());
+    }
+
+    #[test]
+    fn test_shift_quirk_diverges() {
+        let (mut a, mut b) = (machine(Quirks::vip()), machine(Quirks::modern()));
+        let divergence = diff_runs(&mut a, &mut b, &[], 100, 1).unwrap();
+        assert_eq!(3, divergence.cycle);
+        assert_eq!(vec![Difference::Register(0, 0x01, 0x08), Difference::Register(0xF, 1, 0)], divergence.differences);
+        assert_eq!("runs differ after 3 instructions (A / B):
+  V0 01 / 08
+  VF 01 / 00
+leading up to it:
+           1 202 6010 V=00030000000000000000000000000000 I=000 SP=0 DT=00 ST=00  ; LD V0, 0x10
+           2 204 8016 V=10030000000000000000000000000000 I=000 SP=0 DT=00 ST=00  ; SHR V0, V1
+", divergence.to_string());
+    }
+
+    /**
+     * XO-CHIP's extra memory only differs from a 4 KiB machine once
+     * something is put there.
+    */
+    #[test]
+    fn test_memory_sizes_differ() {
+        let mut a = init_chip();
+        a.load_rom_bytes(&[0x12, 0x00]).unwrap();
+        let mut rom = vec![0; 0xE01];
+        rom[..2].copy_from_slice(&[0x12, 0x00]);
+        let mut b = init_chip();
+        b.set_platform(Platform::XoChip);
+        b.load_rom_bytes(&rom).unwrap();
+        assert_eq!(Vec::<Difference>::new(), compare_machines(&a, &b));
+
+        rom[0xE00] = 0xAB;
+        b.load_rom_bytes(&rom).unwrap();
+        assert_eq!(vec![Difference::Memory { address: 0x1000, a: 0, b: 0xAB }], compare_machines(&a, &b));
+        assert_eq!(vec![Difference::Memory { address: 0x1000, a: 0xAB, b: 0 }], compare_machines(&b, &a));
+    }
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /**
+     * Against a trace the difference shows up in the record of the
+     * instruction after the shift.
+     */
+    #[test]
+    fn test_diverges_from_reference_trace() {
+        let out = Shared::default();
+        let mut reference = machine(Quirks::vip());
+        reference.set_tracer(Tracer::new(Box::new(out.clone()), TraceFormat::Text));
+        while !reference.has_exited() {
+            reference.cycle().unwrap();
+        }
+        reference.take_tracer().unwrap().finish().unwrap();
+        let records = trace::read(&out.0.borrow()[..]).unwrap();
+        assert_eq!(5, records.len());
+
+        let mut same = machine(Quirks::vip());
+        assert!(diff_against_trace(&mut same, &records, &[], 2).is_none());
+
+        let mut other = machine(Quirks::modern());
+        let divergence = diff_against_trace(&mut other, &records, &[], 2).unwrap();
+        assert_eq!(3, divergence.cycle);
+        assert_eq!(vec![Difference::Register(0, 0x01, 0x08), Difference::Register(0xF, 1, 0)], divergence.differences);
+        assert_eq!(3, divergence.context.len());
+    }
+}
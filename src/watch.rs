@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::condition::Condition;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write
+}
+
+// A data access made by an instruction: sprite fetches, BCD, register
+// save/load and so on. Instruction fetches are not included; breakpoints
+// cover those.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub address: u32,
+    pub len: u32
+}
+
+impl fmt::Display for MemoryAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind { AccessKind::Read => "read", AccessKind::Write => "write" };
+        write!(f, "{} of {} byte{} at {:03X}", kind, self.len, if self.len == 1 { "" } else { "s" }, self.address)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Watchpoint {
+    // Stops after an instruction reads or writes any of `len` bytes from `start`.
+    Memory { start: u32, len: u32, reads: bool, writes: bool },
+    // Stops when the value of the expression changes, e.g. `V3` or `[0x300]`.
+    Value(Condition),
+    // Stops when the condition becomes true, wherever the program is.
+    Condition(Condition)
+}
+
+impl Watchpoint {
+    pub fn matches(&self, access: &MemoryAccess) -> bool {
+        match *self {
+            Watchpoint::Memory { start, len, reads, writes } => {
+                let wanted = match access.kind { AccessKind::Read => reads, AccessKind::Write => writes };
+                wanted && access.address < start + len && start < access.address + access.len
+            },
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Watchpoint::Memory { start, len, reads, writes } => {
+                let kind = match (reads, writes) { (true, true) => "rw", (true, false) => "r", _ => "w" };
+                write!(f, "memory {:03X}..{:03X} {}", start, start + len - 1, kind)
+            },
+            Watchpoint::Value(expr) => write!(f, "value of {}", expr),
+            Watchpoint::Condition(condition) => write!(f, "when {}", condition)
+        }
+    }
+}